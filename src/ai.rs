@@ -0,0 +1,231 @@
+use avian3d::prelude::LinearVelocity;
+use bevy::{
+    app::{App, FixedUpdate},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::Children,
+        query::{Added, With},
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res},
+    },
+    math::{Vec3, Vec3Swizzles},
+    time::{Time, Timer, TimerMode},
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    entities::{
+        tank_body::TankHandling,
+        turret::{Turret, WeaponState},
+    },
+    tank::{Controller, PlayerId},
+    team::Team,
+    tick::{GameplaySystems, TankCommands},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (attach_ai_controller, drive_ai_tanks)
+            .chain()
+            .in_set(GameplaySystems::Decide),
+    );
+}
+
+/// Distance the AI tries to keep from its target. Any closer and it backs off.
+const AI_PREFERRED_DISTANCE: f32 = 6.;
+const AI_FIRING_RANGE: f32 = 12.;
+/// Minimum seconds between two shots from the same AI tank.
+const AI_FIRE_INTERVAL: f32 = 1.;
+/// How far off, in radians, the hull may point from the target before the AI turns it.
+const AI_STEERING_TOLERANCE: f32 = 0.2;
+/// How far off, in radians, the turret may point from the target and still fire.
+const AI_AIMING_TOLERANCE: f32 = 0.1;
+
+#[derive(Component)]
+pub struct AiController {
+    pub target: Option<Entity>,
+    fire_cooldown: Timer,
+}
+
+impl Default for AiController {
+    fn default() -> Self {
+        Self {
+            target: None,
+            fire_cooldown: Timer::from_seconds(AI_FIRE_INTERVAL, TimerMode::Once),
+        }
+    }
+}
+
+fn attach_ai_controller(
+    mut commands: Commands,
+    controllers: Query<(Entity, &Controller), Added<Controller>>,
+) {
+    for (entity, controller) in controllers.iter() {
+        if *controller == Controller::Program {
+            commands.entity(entity).insert(AiController::default());
+        }
+    }
+}
+
+fn drive_ai_tanks(
+    mut ai_tanks: Query<(
+        &mut AiController,
+        &mut TankCommands,
+        &Team,
+        &GlobalTransform,
+        &Children,
+        &LinearVelocity,
+        &TankHandling,
+    )>,
+    tanks: Query<(Entity, &Team, &GlobalTransform), With<PlayerId>>,
+    turrets: Query<(&GlobalTransform, &WeaponState), With<Turret>>,
+    time: Res<Time>,
+) {
+    for (
+        mut ai_controller,
+        mut tank_commands,
+        team,
+        transform,
+        children,
+        linear_velocity,
+        handling,
+    ) in ai_tanks.iter_mut()
+    {
+        ai_controller.fire_cooldown.tick(time.delta());
+
+        let position = transform.translation();
+
+        ai_controller.target = tanks
+            .iter()
+            .filter(|(_, t, _)| t.id != team.id)
+            .min_by(|(_, _, a), (_, _, b)| {
+                position
+                    .distance_squared(a.translation())
+                    .total_cmp(&position.distance_squared(b.translation()))
+            })
+            .map(|(target, _, _)| target);
+
+        let Some((_, _, target_transform)) = ai_controller.target.and_then(|t| tanks.get(t).ok())
+        else {
+            *tank_commands = TankCommands::default();
+            continue;
+        };
+
+        let target_position = target_transform.translation();
+        let to_target = target_position - position;
+        let distance = to_target.xz().length();
+
+        let hull_angle = signed_angle(transform.forward().as_vec3(), to_target);
+        tank_commands.steer = if hull_angle.abs() > AI_STEERING_TOLERANCE {
+            hull_angle.signum()
+        } else {
+            0.
+        };
+
+        // Brakes early enough to stop at the preferred distance rather than run into the
+        // target, and reverses once inside it.
+        let speed = linear_velocity.dot(transform.forward().as_vec3());
+        let stopping_distance = speed.max(0.).powi(2) / (2. * handling.braking);
+        let gap = distance - AI_PREFERRED_DISTANCE;
+        tank_commands.throttle = if gap <= stopping_distance { -1. } else { 1. };
+        tank_commands.aim = Some(target_position.xz());
+
+        let Some((turret_transform, weapon)) = children.iter().find_map(|&c| turrets.get(c).ok())
+        else {
+            continue;
+        };
+
+        let turret_angle = signed_angle(
+            turret_transform.forward().as_vec3(),
+            target_position - turret_transform.translation(),
+        );
+
+        if distance <= AI_FIRING_RANGE
+            && turret_angle.abs() <= AI_AIMING_TOLERANCE
+            && ai_controller.fire_cooldown.is_finished()
+            && weapon.can_fire()
+        {
+            tank_commands.fire = true;
+            ai_controller.fire_cooldown.reset();
+        }
+    }
+}
+
+/// Angle in the x/z plane from `from` to `to`, positive when `to` is to the left of `from`.
+fn signed_angle(from: Vec3, to: Vec3) -> f32 {
+    let from = from.xz();
+    let to = to.xz();
+
+    // x/z has the opposite handedness to x/y, so a left turn about +Y is a negative perp dot.
+    (-from.perp_dot(to)).atan2(from.dot(to))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{MinimalPlugins, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::{
+        SimulationPlugin,
+        combat::Scoreboard,
+        state::{MatchPlayer, MatchSettings},
+        team::TeamId,
+    };
+
+    #[test]
+    fn ai_tanks_fight_it_out_on_the_arena() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, SimulationPlugin { headless: true }))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                50,
+            )));
+        app.finish();
+        app.cleanup();
+
+        {
+            let mut match_settings = app.world_mut().resource_mut::<MatchSettings>();
+            match_settings.map = "Arena".to_string();
+            match_settings.players = (0..2)
+                .map(|team| MatchPlayer {
+                    controller: Controller::Program,
+                    team: TeamId(team),
+                })
+                .collect();
+        }
+
+        // A minute of match time.
+        let mut closest = f32::INFINITY;
+        for _ in 0..1200 {
+            app.update();
+
+            let positions: Vec<Vec3> = app
+                .world_mut()
+                .query_filtered::<&GlobalTransform, With<PlayerId>>()
+                .iter(app.world())
+                .map(|t| t.translation())
+                .collect();
+            if let [a, b] = positions[..] {
+                closest = closest.min(a.xz().distance(b.xz()));
+            }
+        }
+
+        // Without braking they'd drive into each other and sit there.
+        assert!(
+            closest > AI_PREFERRED_DISTANCE / 2.,
+            "tanks came within {closest}"
+        );
+
+        let scoreboard = app.world().resource::<Scoreboard>();
+        for player in [PlayerId(0), PlayerId(1)] {
+            assert!(
+                scoreboard.score(player).damage_dealt > 0,
+                "player {} never landed a hit",
+                player.0
+            );
+        }
+    }
+}
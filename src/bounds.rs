@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        message::{Message, MessageReader, MessageWriter},
+        query::Without,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res},
+    },
+    log::info,
+    time::{Time, Timer, TimerMode},
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    maps::MapBounds,
+    systems::despawn_entity::DespawnEntity,
+    tank::{DeathCause, Health, PlayerId},
+    tick::GameplaySystems,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<OutOfBoundsSettings>()
+        .add_message::<OutOfBoundsWarning>()
+        .add_systems(
+            FixedUpdate,
+            enforce_map_bounds.in_set(GameplaySystems::Simulate),
+        )
+        .add_systems(Update, log_out_of_bounds_warnings);
+}
+
+#[derive(Resource)]
+pub struct OutOfBoundsSettings {
+    /// How long a tank may stay outside the map's bounds before it is destroyed.
+    pub grace: Duration,
+}
+
+impl Default for OutOfBoundsSettings {
+    fn default() -> Self {
+        Self {
+            grace: Duration::from_secs(3),
+        }
+    }
+}
+
+/// A tank outside its map's bounds, counting down until it is destroyed.
+#[derive(Component)]
+pub struct OutOfBounds {
+    pub timer: Timer,
+}
+
+/// Sent once when a tank leaves the map's bounds.
+#[derive(Message)]
+pub struct OutOfBoundsWarning {
+    pub tank: Entity,
+    pub player: PlayerId,
+    /// How long it has to get back in.
+    pub time_left: Duration,
+}
+
+/// Destroys tanks that fall below the map's kill plane straight away, and those that stay out
+/// of its bounds once their grace runs out.
+#[allow(clippy::type_complexity)]
+fn enforce_map_bounds(
+    mut commands: Commands,
+    mut out_of_bounds_warning_event_writer: MessageWriter<OutOfBoundsWarning>,
+    mut tanks: Query<
+        (
+            Entity,
+            &PlayerId,
+            &GlobalTransform,
+            &mut Health,
+            &mut DeathCause,
+            Option<&mut OutOfBounds>,
+        ),
+        Without<DespawnEntity>,
+    >,
+    maps: Query<(&MapBounds, &GlobalTransform), Without<DespawnEntity>>,
+    out_of_bounds_settings: Res<OutOfBoundsSettings>,
+    time: Res<Time>,
+) {
+    // Between maps there are no bounds to leave.
+    let Some((bounds, map_transform)) = maps.iter().next() else {
+        return;
+    };
+
+    let to_map = map_transform.affine().inverse();
+
+    for (tank, player, transform, mut health, mut death_cause, out_of_bounds) in tanks.iter_mut() {
+        if health.0 == 0 {
+            continue;
+        }
+
+        let position = to_map.transform_point3(transform.translation());
+
+        if position.y < bounds.kill_height {
+            *death_cause = DeathCause::Fell;
+            health.0 = 0;
+            continue;
+        }
+
+        match (bounds.contains(position), out_of_bounds) {
+            (true, Some(_)) => {
+                commands.entity(tank).remove::<OutOfBounds>();
+            }
+            (false, None) => {
+                commands.entity(tank).insert(OutOfBounds {
+                    timer: Timer::new(out_of_bounds_settings.grace, TimerMode::Once),
+                });
+                out_of_bounds_warning_event_writer.write(OutOfBoundsWarning {
+                    tank,
+                    player: *player,
+                    time_left: out_of_bounds_settings.grace,
+                });
+            }
+            (false, Some(mut out_of_bounds)) => {
+                out_of_bounds.timer.tick(time.delta());
+
+                if out_of_bounds.timer.is_finished() {
+                    *death_cause = DeathCause::OutOfBounds;
+                    health.0 = 0;
+                }
+            }
+            (true, None) => {}
+        }
+    }
+}
+
+fn log_out_of_bounds_warnings(
+    mut out_of_bounds_warning_event_reader: MessageReader<OutOfBoundsWarning>,
+) {
+    for event in out_of_bounds_warning_event_reader.read() {
+        info!(
+            "Player {} left the map, {:.1}s to get back",
+            event.player.0,
+            event.time_left.as_secs_f32()
+        );
+    }
+}
@@ -1,24 +1,432 @@
-use bevy::{
-    app::{App, Startup},
-    camera::Camera3d,
-    ecs::{component::Component, system::Commands},
-    math::Vec3,
-    transform::components::Transform,
-};
-
-#[derive(Component)]
-pub struct MainCamera;
-
-pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Startup, initialize_camera);
-}
-
-fn initialize_camera(mut commands: Commands) {
-    commands.spawn((
-        Camera3d {
-            ..Default::default()
-        },
-        Transform::from_xyz(0., 10., 10.).looking_at(Vec3::ZERO, Vec3::Y),
-        MainCamera,
-    ));
-}
+use bevy::{
+    app::{App, Startup, Update},
+    camera::{Camera, Camera2d, Camera3d, ClearColorConfig, Viewport},
+    ecs::{
+        component::Component,
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{
+        ButtonInput,
+        keyboard::KeyCode,
+        mouse::{AccumulatedMouseScroll, MouseScrollUnit},
+    },
+    math::{StableInterpolate, UVec2, Vec2, Vec3, Vec3Swizzles},
+    state::{
+        condition::in_state,
+        state::{OnEnter, OnExit},
+        state_scoped::DespawnOnExit,
+    },
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+    ui::IsDefaultUiCamera,
+    window::Window,
+};
+
+use crate::{
+    maps::MapBounds,
+    state::{GameState, MatchSettings},
+    systems::despawn_entity::DespawnEntity,
+    tank::{Controller, LocalPlayer, PlayerId},
+    tick::TankCommands,
+};
+
+/// The shared view, of the whole map or of every local tank at once.
+#[derive(Component)]
+pub struct MainCamera;
+
+/// The view of one local player's part of a split screen.
+#[derive(Component)]
+pub struct SeatCamera(pub LocalPlayer);
+
+/// The point a camera looks at, which trails behind where it wants to look.
+#[derive(Component, Default)]
+pub struct CameraFocus(pub Vec3);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    /// Each view chases its tank, a little ahead of it towards where it aims.
+    #[default]
+    Follow,
+    /// One view of the whole map.
+    FixedArena,
+    /// One view, moved around by hand.
+    FreeFly,
+}
+
+impl CameraMode {
+    fn next(self) -> Self {
+        match self {
+            CameraMode::Follow => CameraMode::FixedArena,
+            CameraMode::FixedArena => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Follow,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraController {
+    pub mode: CameraMode,
+    /// How far back the cameras sit, as a multiple of their usual distance.
+    pub zoom: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::default(),
+            zoom: 1.,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct CameraSettings {
+    /// How quickly a camera catches up with what it follows. Higher is snappier.
+    pub damping: f32,
+    /// How far ahead of a tank, towards its aim, a following camera looks.
+    pub lead: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// How much one step of the mouse wheel zooms.
+    pub zoom_step: f32,
+    /// How fast a free-flying camera moves, at a zoom of 1.
+    pub free_fly_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            damping: 6.,
+            lead: 3.,
+            min_zoom: 0.5,
+            max_zoom: 3.,
+            zoom_step: 0.1,
+            free_fly_speed: 10.,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SplitScreenSettings {
+    /// Whether the split views merge into the shared one while the local tanks are close
+    /// together.
+    pub merge: bool,
+    /// How close together every local tank has to be for the views to merge.
+    pub merge_distance: f32,
+    /// How far apart the local tanks get before merged views split again. Above
+    /// `merge_distance`, so they don't flicker between the two.
+    pub split_distance: f32,
+}
+
+impl Default for SplitScreenSettings {
+    fn default() -> Self {
+        Self {
+            merge: true,
+            merge_distance: 6.,
+            split_distance: 10.,
+        }
+    }
+}
+
+/// Where a camera sits relative to what it looks at.
+const CAMERA_OFFSET: Vec3 = Vec3::new(0., 10., 10.);
+
+/// How much of a map one camera takes in at a zoom of 1.
+const CAMERA_VIEW_SIZE: f32 = 10.;
+
+/// Draws the menus over every other camera.
+const UI_CAMERA_ORDER: isize = 100;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SplitScreenSettings>()
+        .init_resource::<CameraController>()
+        .init_resource::<CameraSettings>()
+        .add_systems(Startup, initialize_camera)
+        .add_systems(OnEnter(GameState::InMatch), spawn_seat_cameras)
+        .add_systems(OnExit(GameState::InMatch), reset_main_camera)
+        .add_systems(
+            Update,
+            (camera_input, layout_split_screen, move_cameras)
+                .chain()
+                .run_if(in_state(GameState::InMatch)),
+        );
+}
+
+fn initialize_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera3d {
+            ..Default::default()
+        },
+        Transform::from_translation(CAMERA_OFFSET).looking_at(Vec3::ZERO, Vec3::Y),
+        CameraFocus::default(),
+        MainCamera,
+    ));
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: UI_CAMERA_ORDER,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        IsDefaultUiCamera,
+    ));
+}
+
+fn spawn_seat_cameras(mut commands: Commands, match_settings: Res<MatchSettings>) {
+    let local_players = match_settings
+        .players
+        .iter()
+        .filter_map(|p| match p.controller {
+            Controller::User(local_player) => Some(local_player),
+            Controller::Program => None,
+        });
+
+    for (index, local_player) in local_players.enumerate() {
+        commands.spawn((
+            Camera3d {
+                ..Default::default()
+            },
+            Camera {
+                order: 1 + index as isize,
+                is_active: false,
+                ..Default::default()
+            },
+            Transform::from_translation(CAMERA_OFFSET).looking_at(Vec3::ZERO, Vec3::Y),
+            CameraFocus::default(),
+            SeatCamera(local_player),
+            DespawnOnExit(GameState::InMatch),
+        ));
+    }
+}
+
+fn reset_main_camera(
+    mut main_camera: Query<(&mut Camera, &mut Transform, &mut CameraFocus), With<MainCamera>>,
+) {
+    for (mut camera, mut transform, mut focus) in main_camera.iter_mut() {
+        camera.is_active = true;
+        focus.0 = Vec3::ZERO;
+        *transform = Transform::from_translation(CAMERA_OFFSET).looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
+
+/// Where every tank played from this machine is.
+fn local_tank_positions(
+    tanks: &Query<(&Controller, &GlobalTransform), With<PlayerId>>,
+) -> Vec<Vec3> {
+    tanks
+        .iter()
+        .filter(|(controller, _)| matches!(controller, Controller::User(_)))
+        .map(|(_, transform)| transform.translation())
+        .collect()
+}
+
+/// The greatest distance across the map between any two of `positions`.
+fn spread(positions: &[Vec3]) -> f32 {
+    positions
+        .iter()
+        .flat_map(|a| positions.iter().map(move |b| a.xz().distance(b.xz())))
+        .fold(0., f32::max)
+}
+
+/// The `index`th of `seats` views in a grid over the window. A last row that isn't full is
+/// shared out across the whole width.
+fn seat_viewport(index: usize, seats: usize, window_size: UVec2) -> Viewport {
+    let columns = (seats as f32).sqrt().ceil() as usize;
+    let rows = seats.div_ceil(columns);
+    let row = index / columns;
+    let in_row = if row + 1 == rows {
+        seats - row * columns
+    } else {
+        columns
+    };
+
+    let size = UVec2::new(window_size.x / in_row as u32, window_size.y / rows as u32);
+
+    Viewport {
+        physical_position: UVec2::new(size.x * (index % columns) as u32, size.y * row as u32),
+        physical_size: size.max(UVec2::ONE),
+        ..Default::default()
+    }
+}
+
+/// C switches camera modes, the mouse wheel zooms and the arrow keys fly a free camera.
+fn camera_input(
+    mut camera_controller: ResMut<CameraController>,
+    mut main_camera: Query<&mut CameraFocus, With<MainCamera>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    camera_settings: Res<CameraSettings>,
+    time: Res<Time>,
+) {
+    if keys.just_pressed(KeyCode::KeyC) {
+        camera_controller.mode = camera_controller.mode.next();
+    }
+
+    let scroll_steps = match mouse_scroll.unit {
+        MouseScrollUnit::Line => mouse_scroll.delta.y,
+        MouseScrollUnit::Pixel => mouse_scroll.delta.y / 100.,
+    };
+    if scroll_steps != 0. {
+        camera_controller.zoom = (camera_controller.zoom
+            - scroll_steps * camera_settings.zoom_step)
+            .clamp(camera_settings.min_zoom, camera_settings.max_zoom);
+    }
+
+    if camera_controller.mode != CameraMode::FreeFly {
+        return;
+    }
+
+    let pan = Vec2::new(
+        f32::from(keys.pressed(KeyCode::ArrowRight)) - f32::from(keys.pressed(KeyCode::ArrowLeft)),
+        f32::from(keys.pressed(KeyCode::ArrowDown)) - f32::from(keys.pressed(KeyCode::ArrowUp)),
+    )
+    .normalize_or_zero();
+    let distance = camera_settings.free_fly_speed * camera_controller.zoom * time.delta_secs();
+
+    for mut focus in main_camera.iter_mut() {
+        focus.0 += Vec3::new(pan.x, 0., pan.y) * distance;
+    }
+}
+
+/// Shows the shared view while there is one local player or their tanks are close together,
+/// and a view for each of them otherwise.
+fn layout_split_screen(
+    mut main_camera: Query<&mut Camera, (With<MainCamera>, Without<SeatCamera>)>,
+    mut seat_cameras: Query<(&mut Camera, &SeatCamera), Without<MainCamera>>,
+    tanks: Query<(&Controller, &GlobalTransform), With<PlayerId>>,
+    windows: Query<&Window>,
+    split_screen_settings: Res<SplitScreenSettings>,
+    camera_controller: Res<CameraController>,
+) {
+    let (Ok(mut main_camera), Ok(window)) = (main_camera.single_mut(), windows.single()) else {
+        return;
+    };
+
+    let mut seat_cameras: Vec<_> = seat_cameras.iter_mut().collect();
+    seat_cameras.sort_by_key(|(_, seat)| seat.0.0);
+
+    let spread = spread(&local_tank_positions(&tanks));
+    // Only following gives each player a view of their own.
+    let merged = if seat_cameras.len() <= 1 || camera_controller.mode != CameraMode::Follow {
+        true
+    } else if !split_screen_settings.merge {
+        false
+    } else if main_camera.is_active {
+        spread <= split_screen_settings.split_distance
+    } else {
+        spread <= split_screen_settings.merge_distance
+    };
+
+    if main_camera.is_active != merged {
+        main_camera.is_active = merged;
+    }
+
+    let seats = seat_cameras.len();
+    for (index, (camera, _)) in seat_cameras.iter_mut().enumerate() {
+        let viewport = seat_viewport(index, seats, window.physical_size());
+
+        if camera.is_active == merged {
+            camera.is_active = !merged;
+        }
+        if camera.viewport.as_ref().is_none_or(|v| {
+            (v.physical_position, v.physical_size)
+                != (viewport.physical_position, viewport.physical_size)
+        }) {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+/// Where a tank is, and how far ahead of it towards its aim to look.
+fn lead_target(
+    transform: &GlobalTransform,
+    tank_commands: &TankCommands,
+    camera_settings: &CameraSettings,
+) -> Vec3 {
+    let position = transform.translation();
+    let lead = tank_commands.aim.map_or(Vec2::ZERO, |aim| {
+        (aim - position.xz()).clamp_length_max(camera_settings.lead)
+    });
+
+    position + Vec3::new(lead.x, 0., lead.y)
+}
+
+/// Eases every camera towards what its mode has it look at, kept over the map.
+fn move_cameras(
+    mut cameras: Query<(&mut Transform, &mut CameraFocus, Option<&SeatCamera>)>,
+    tanks: Query<(&Controller, &GlobalTransform, &TankCommands), With<PlayerId>>,
+    maps: Query<(&MapBounds, &GlobalTransform), Without<DespawnEntity>>,
+    camera_controller: Res<CameraController>,
+    camera_settings: Res<CameraSettings>,
+    split_screen_settings: Res<SplitScreenSettings>,
+    time: Res<Time>,
+) {
+    let map = maps.iter().next();
+    let (map_centre, map_zoom) = map.map_or((Vec3::ZERO, 1.), |(bounds, transform)| {
+        (
+            transform.translation(),
+            (bounds.size.max_element() / CAMERA_VIEW_SIZE).max(1.),
+        )
+    });
+
+    let local_tanks: Vec<(LocalPlayer, Vec3, Vec3)> = tanks
+        .iter()
+        .filter_map(|(controller, transform, tank_commands)| match controller {
+            Controller::User(local_player) => Some((
+                *local_player,
+                transform.translation(),
+                lead_target(transform, tank_commands, &camera_settings),
+            )),
+            Controller::Program => None,
+        })
+        .collect();
+
+    for (mut transform, mut focus, seat) in cameras.iter_mut() {
+        let (target, zoom) = match (camera_controller.mode, seat) {
+            (CameraMode::Follow, Some(seat)) => (
+                local_tanks
+                    .iter()
+                    .find(|(local_player, ..)| *local_player == seat.0)
+                    .map_or(focus.0, |(.., target)| *target),
+                1.,
+            ),
+            // Backs off as the tanks spread out, so the ones at the edges stay in view.
+            (CameraMode::Follow, None) if !local_tanks.is_empty() => {
+                let positions: Vec<Vec3> = local_tanks.iter().map(|(_, p, _)| *p).collect();
+                (
+                    local_tanks.iter().map(|(.., t)| *t).sum::<Vec3>() / local_tanks.len() as f32,
+                    1. + spread(&positions) / split_screen_settings.split_distance,
+                )
+            }
+            (CameraMode::Follow | CameraMode::FixedArena, _) => (map_centre, map_zoom),
+            (CameraMode::FreeFly, _) => (focus.0, 1.),
+        };
+
+        let target = match map {
+            Some((bounds, map_transform)) => {
+                let mut local = map_transform.affine().inverse().transform_point3(target);
+                local.x = local.x.clamp(-bounds.size.x / 2., bounds.size.x / 2.);
+                local.z = local.z.clamp(-bounds.size.y / 2., bounds.size.y / 2.);
+                map_transform.transform_point(local)
+            }
+            None => target,
+        };
+
+        // A free camera already moves smoothly, and easing it back would let it drift off the map.
+        if camera_controller.mode == CameraMode::FreeFly {
+            focus.0 = target;
+        } else {
+            focus
+                .0
+                .smooth_nudge(&target, camera_settings.damping, time.delta_secs());
+        }
+
+        *transform =
+            Transform::from_translation(focus.0 + CAMERA_OFFSET * zoom * camera_controller.zoom)
+                .looking_at(focus.0, Vec3::Y);
+    }
+}
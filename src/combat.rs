@@ -0,0 +1,364 @@
+use avian3d::prelude::{CollisionStart, Collisions, PhysicsSystems};
+use bevy::{
+    app::{App, FixedPostUpdate, FixedUpdate},
+    asset::Handle,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        message::{Message, MessageReader, MessageWriter},
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Query, ResMut},
+    },
+    math::Vec3,
+    platform::collections::HashMap,
+    state::state::OnEnter,
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    entities::{
+        bullet::{Bullet, contact},
+        turret::definition::TurretDefinition,
+    },
+    state::GameState,
+    tank::{Health, PlayerId, TankDestroyed},
+    tick::GameplaySystems,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Scoreboard>()
+        .add_message::<TankHit>()
+        .add_systems(OnEnter(GameState::InMatch), reset_scoreboard)
+        .add_systems(
+            FixedPostUpdate,
+            detect_tank_hits.after(PhysicsSystems::StepSimulation),
+        )
+        .add_systems(
+            FixedUpdate,
+            (apply_tank_hits, score_destroyed_tanks).in_set(GameplaySystems::Simulate),
+        );
+}
+
+/// Who fired a projectile, and with what.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Shooter {
+    pub tank: Entity,
+    pub player: PlayerId,
+    pub weapon: Handle<TurretDefinition>,
+}
+
+/// A projectile hit a tank, or any collider that belongs to one.
+#[derive(Message, Clone, Debug)]
+pub struct TankHit {
+    pub shooter: Shooter,
+    pub victim: Entity,
+    pub damage: u8,
+    pub point: Vec3,
+}
+
+/// Damage a tank has taken over its current life, and from whom.
+#[derive(Component, Default)]
+pub struct DamageLedger {
+    pub entries: Vec<DamageEntry>,
+    /// The shot that most recently did damage, which is credited with the kill.
+    pub last_hit: Option<Shooter>,
+}
+
+pub struct DamageEntry {
+    pub player: PlayerId,
+    pub weapon: Handle<TurretDefinition>,
+    pub damage: u32,
+}
+
+impl DamageLedger {
+    pub fn record(&mut self, shooter: &Shooter, damage: u32) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.player == shooter.player && e.weapon == shooter.weapon)
+        {
+            Some(entry) => entry.damage += damage,
+            None => self.entries.push(DamageEntry {
+                player: shooter.player,
+                weapon: shooter.weapon.clone(),
+                damage,
+            }),
+        }
+
+        self.last_hit = Some(shooter.clone());
+    }
+
+    pub fn damage_by(&self, player: PlayerId) -> u32 {
+        self.entries
+            .iter()
+            .filter(|e| e.player == player)
+            .map(|e| e.damage)
+            .sum()
+    }
+
+    /// Everyone but the victim and the killer who did damage, most damage first.
+    pub fn assists(&self, victim: PlayerId, killer: Option<PlayerId>) -> Vec<PlayerId> {
+        let mut assists: Vec<PlayerId> = Vec::new();
+
+        for entry in self.entries.iter() {
+            if entry.player != victim
+                && Some(entry.player) != killer
+                && !assists.contains(&entry.player)
+            {
+                assists.push(entry.player);
+            }
+        }
+
+        assists.sort_by_key(|&p| std::cmp::Reverse(self.damage_by(p)));
+        assists
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Score {
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub damage_dealt: u32,
+}
+
+/// Every player's score over the match so far.
+#[derive(Resource, Default)]
+pub struct Scoreboard {
+    pub players: HashMap<PlayerId, Score>,
+}
+
+impl Scoreboard {
+    pub fn score(&self, player: PlayerId) -> Score {
+        self.players.get(&player).copied().unwrap_or_default()
+    }
+}
+
+/// The tank `entity` belongs to, which is either the entity itself or one of its ancestors.
+pub fn owning_tank(
+    entity: Entity,
+    parents: &Query<&ChildOf>,
+    tanks: &Query<(), With<PlayerId>>,
+) -> Option<Entity> {
+    std::iter::once(entity)
+        .chain(parents.iter_ancestors(entity))
+        .find(|&e| tanks.contains(e))
+}
+
+fn reset_scoreboard(mut scoreboard: ResMut<Scoreboard>) {
+    *scoreboard = Scoreboard::default();
+}
+
+fn detect_tank_hits(
+    mut collision_event_reader: MessageReader<CollisionStart>,
+    mut tank_hit_event_writer: MessageWriter<TankHit>,
+    collisions: Collisions,
+    bullets: Query<(&Bullet, &GlobalTransform)>,
+    parents: Query<&ChildOf>,
+    tanks: Query<(), With<PlayerId>>,
+) {
+    for event in collision_event_reader.read() {
+        // Colliders come in no particular order, so try the bullet on both sides.
+        for (projectile, surface) in [
+            (event.collider1, event.collider2),
+            (event.collider2, event.collider1),
+        ] {
+            if let Ok((bullet, transform)) = bullets.get(projectile)
+                && let Some(victim) = owning_tank(surface, &parents, &tanks)
+            {
+                let point = contact(&collisions, projectile, surface)
+                    .map_or(transform.translation(), |(point, _)| point);
+
+                tank_hit_event_writer.write(TankHit {
+                    shooter: bullet.shooter.clone(),
+                    victim,
+                    damage: bullet.damage,
+                    point,
+                });
+            }
+        }
+    }
+}
+
+fn apply_tank_hits(
+    mut tank_hit_event_reader: MessageReader<TankHit>,
+    mut tanks: Query<(&mut Health, &mut DamageLedger)>,
+    mut scoreboard: ResMut<Scoreboard>,
+) {
+    for event in tank_hit_event_reader.read() {
+        if let Ok((mut health, mut damage_ledger)) = tanks.get_mut(event.victim)
+            && health.0 > 0
+        {
+            // Only the health actually taken counts, not what the shot could have done.
+            let damage = event.damage.min(health.0);
+            health.0 -= damage;
+
+            damage_ledger.record(&event.shooter, damage as u32);
+            scoreboard
+                .players
+                .entry(event.shooter.player)
+                .or_default()
+                .damage_dealt += damage as u32;
+        }
+    }
+}
+
+fn score_destroyed_tanks(
+    mut tank_destroyed_event_reader: MessageReader<TankDestroyed>,
+    mut scoreboard: ResMut<Scoreboard>,
+) {
+    for event in tank_destroyed_event_reader.read() {
+        scoreboard.players.entry(event.player).or_default().deaths += 1;
+
+        if let Some(killer) = &event.killer {
+            scoreboard.players.entry(killer.player).or_default().kills += 1;
+        }
+
+        for assist in event.assists.iter() {
+            scoreboard.players.entry(*assist).or_default().assists += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::{App, Update},
+        asset::uuid_handle,
+        color::Color,
+    };
+
+    use super::*;
+    use crate::{
+        tank::{Controller, DeathCause, Loadout, LoadoutDefinition},
+        team::{Team, TeamId},
+    };
+
+    fn shooter(player: u8) -> Shooter {
+        Shooter {
+            tank: Entity::PLACEHOLDER,
+            player: PlayerId(player),
+            weapon: Handle::default(),
+        }
+    }
+
+    #[test]
+    fn damage_adds_up_per_player_and_weapon() {
+        let other_weapon = Shooter {
+            weapon: uuid_handle!("6b1c6c67-3b8e-4d47-9f0e-2f5b4a0f6a11"),
+            ..shooter(1)
+        };
+
+        let mut damage_ledger = DamageLedger::default();
+        damage_ledger.record(&shooter(1), 10);
+        damage_ledger.record(&shooter(1), 5);
+        damage_ledger.record(&other_weapon, 20);
+        damage_ledger.record(&shooter(2), 7);
+
+        assert_eq!(damage_ledger.entries.len(), 3);
+        assert_eq!(damage_ledger.damage_by(PlayerId(1)), 35);
+        assert_eq!(damage_ledger.damage_by(PlayerId(2)), 7);
+        assert_eq!(damage_ledger.damage_by(PlayerId(3)), 0);
+        assert_eq!(damage_ledger.last_hit, Some(shooter(2)));
+    }
+
+    #[test]
+    fn assists_leave_out_victim_and_killer_most_damage_first() {
+        let mut damage_ledger = DamageLedger::default();
+        damage_ledger.record(&shooter(0), 50);
+        damage_ledger.record(&shooter(1), 10);
+        damage_ledger.record(&shooter(2), 30);
+        damage_ledger.record(&shooter(3), 20);
+        damage_ledger.record(&shooter(1), 25);
+
+        assert_eq!(
+            damage_ledger.assists(PlayerId(0), Some(PlayerId(3))),
+            vec![PlayerId(1), PlayerId(2)]
+        );
+        assert_eq!(
+            damage_ledger.assists(PlayerId(0), None),
+            vec![PlayerId(1), PlayerId(2), PlayerId(3)]
+        );
+    }
+
+    fn scoring_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Scoreboard>()
+            .add_message::<TankHit>()
+            .add_message::<TankDestroyed>()
+            .add_systems(Update, (apply_tank_hits, score_destroyed_tanks));
+
+        app
+    }
+
+    #[test]
+    fn only_health_taken_counts_as_damage_dealt() {
+        let mut app = scoring_app();
+        let victim = app
+            .world_mut()
+            .spawn((Health(30), DamageLedger::default()))
+            .id();
+
+        for _ in 0..2 {
+            app.world_mut().write_message(TankHit {
+                shooter: shooter(1),
+                victim,
+                damage: 20,
+                point: Vec3::ZERO,
+            });
+        }
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(victim).unwrap().0, 0);
+        assert_eq!(
+            app.world()
+                .get::<DamageLedger>(victim)
+                .unwrap()
+                .damage_by(PlayerId(1)),
+            30
+        );
+
+        let scoreboard = app.world().resource::<Scoreboard>();
+        assert_eq!(scoreboard.score(PlayerId(1)).damage_dealt, 30);
+        assert_eq!(scoreboard.score(PlayerId(0)).damage_dealt, 0);
+    }
+
+    #[test]
+    fn destroyed_tanks_credit_kill_death_and_assists() {
+        let mut app = scoring_app();
+        let loadout = Loadout::from(&LoadoutDefinition {
+            turret: "turrets/basic.turret.ron".into(),
+            tank_body: "tank_bodies/basic.tank_body.ron".into(),
+        });
+        let tank_destroyed = |killer: Option<Shooter>| TankDestroyed {
+            tank: Entity::PLACEHOLDER,
+            player: PlayerId(0),
+            controller: Controller::Program,
+            team: Team {
+                id: TeamId(0),
+                color: Color::WHITE,
+            },
+            loadout: loadout.clone(),
+            cause: DeathCause::Destroyed,
+            killer,
+            assists: vec![PlayerId(2)],
+        };
+
+        app.world_mut()
+            .write_message(tank_destroyed(Some(shooter(1))));
+        app.world_mut().write_message(tank_destroyed(None));
+        app.update();
+
+        let scoreboard = app.world().resource::<Scoreboard>();
+        let victim = scoreboard.score(PlayerId(0));
+        let killer = scoreboard.score(PlayerId(1));
+        let assist = scoreboard.score(PlayerId(2));
+
+        assert_eq!((victim.kills, victim.deaths), (0, 2));
+        assert_eq!((killer.kills, killer.deaths, killer.assists), (1, 0, 0));
+        assert_eq!(assist.assists, 2);
+    }
+}
@@ -1,12 +1,189 @@
-use bevy::ecs::component::Component;
-
-#[derive(Component)]
-pub struct Bullet {
-    pub damage: u8,
-}
-
-impl Bullet {
-    pub fn new(damage: u8) -> Self {
-        Self { damage }
-    }
-}
+use avian3d::prelude::{
+    CollisionLayers, CollisionStart, Collisions, PhysicsLayer, PhysicsSystems, Sensor,
+};
+use bevy::{
+    app::{App, FixedPostUpdate, FixedUpdate},
+    color::Color,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        message::{Message, MessageReader, MessageWriter},
+        query::{With, Without},
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res},
+    },
+    math::Vec3,
+    time::{Time, Timer},
+    transform::components::GlobalTransform,
+};
+use serde::Deserialize;
+
+use crate::{
+    combat::{Shooter, owning_tank},
+    systems::despawn_entity::DespawnEntity,
+    tank::PlayerId,
+    team::{Team, TeamId},
+    tick::GameplaySystems,
+};
+
+pub fn plugin(app: &mut App) {
+    app.add_message::<ProjectileImpact>()
+        .add_systems(
+            FixedUpdate,
+            expire_projectiles.in_set(GameplaySystems::Simulate),
+        )
+        .add_systems(
+            FixedPostUpdate,
+            detect_projectile_impacts.after(PhysicsSystems::StepSimulation),
+        );
+}
+
+#[derive(Component)]
+pub struct Bullet {
+    pub damage: u8,
+    pub shooter: Shooter,
+    pub team: TeamId,
+    pub paint: Color,
+}
+
+impl Bullet {
+    pub fn new(damage: u8, shooter: Shooter, team: &Team) -> Self {
+        Self {
+            damage,
+            shooter,
+            team: team.id,
+            paint: team.color,
+        }
+    }
+}
+
+/// Which impacts end a projectile.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DespawnOnImpact {
+    /// The first thing it touches.
+    #[default]
+    Always,
+    /// Only tanks, it bounces off everything else.
+    TanksOnly,
+    /// Nothing, it lives out its lifetime or range.
+    Never,
+}
+
+/// Which colliders can touch. Anything without `CollisionLayers` is on `Default`.
+#[derive(PhysicsLayer, Default)]
+pub enum GameLayer {
+    #[default]
+    Default,
+    /// Passes through other projectiles, so shots fired back along each other's path still land.
+    Projectile,
+}
+
+/// How long a projectile may stay in the world, and what ends it early.
+#[derive(Component)]
+#[require(CollisionLayers = CollisionLayers::new(GameLayer::Projectile, GameLayer::Default))]
+pub struct Projectile {
+    pub lifetime: Timer,
+    /// Where the projectile was fired from, to measure how far it has travelled.
+    pub origin: Vec3,
+    pub max_distance: f32,
+    pub despawn_on_impact: DespawnOnImpact,
+}
+
+/// A projectile touched something.
+#[derive(Message)]
+pub struct ProjectileImpact {
+    /// The entity that was hit.
+    pub surface: Entity,
+    pub position: Vec3,
+    /// Points out of the surface, back towards the projectile.
+    pub normal: Vec3,
+    pub team: TeamId,
+    pub color: Color,
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &mut Projectile, &GlobalTransform), Without<DespawnEntity>>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, transform) in projectiles.iter_mut() {
+        projectile.lifetime.tick(time.delta());
+
+        if projectile.lifetime.is_finished()
+            || transform.translation().distance(projectile.origin) > projectile.max_distance
+        {
+            commands.entity(entity).insert(DespawnEntity);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detect_projectile_impacts(
+    mut commands: Commands,
+    mut collision_event_reader: MessageReader<CollisionStart>,
+    mut projectile_impact_event_writer: MessageWriter<ProjectileImpact>,
+    collisions: Collisions,
+    projectiles: Query<(&Bullet, &Projectile)>,
+    sensors: Query<(), With<Sensor>>,
+    parents: Query<&ChildOf>,
+    tanks: Query<(), With<PlayerId>>,
+) {
+    for event in collision_event_reader.read() {
+        // Sensors such as capture zones only notice what passes through them.
+        if sensors.contains(event.collider1) || sensors.contains(event.collider2) {
+            continue;
+        }
+
+        // Either collider may be the projectile, or both may be.
+        for (projectile_entity, surface) in [
+            (event.collider1, event.collider2),
+            (event.collider2, event.collider1),
+        ] {
+            let Ok((bullet, projectile)) = projectiles.get(projectile_entity) else {
+                continue;
+            };
+
+            if let Some((position, normal)) = contact(&collisions, projectile_entity, surface) {
+                projectile_impact_event_writer.write(ProjectileImpact {
+                    surface,
+                    position,
+                    normal,
+                    team: bullet.team,
+                    color: bullet.paint,
+                });
+            }
+
+            let despawn = match projectile.despawn_on_impact {
+                DespawnOnImpact::Always => true,
+                DespawnOnImpact::TanksOnly => owning_tank(surface, &parents, &tanks).is_some(),
+                DespawnOnImpact::Never => false,
+            };
+
+            if despawn {
+                commands.entity(projectile_entity).insert(DespawnEntity);
+            }
+        }
+    }
+}
+
+/// Deepest contact point between a projectile and what it hit, with the normal pointing out
+/// of `surface`.
+pub(crate) fn contact(
+    collisions: &Collisions,
+    projectile: Entity,
+    surface: Entity,
+) -> Option<(Vec3, Vec3)> {
+    let contact_pair = collisions.get(projectile, surface)?;
+    let manifold = contact_pair.manifolds.first()?;
+    let contact = manifold.find_deepest_contact()?;
+
+    // Manifold normals point from the pair's first collider to its second.
+    let normal = if contact_pair.collider1 == surface {
+        manifold.normal
+    } else {
+        -manifold.normal
+    };
+
+    Some((contact.point, normal))
+}
@@ -0,0 +1,55 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{Asset, AssetLoader, LoadContext, io::Reader},
+    ecs::error::BevyError,
+};
+use serde::de::DeserializeOwned;
+
+/// A designer-authored asset, checked once when it loads so bad values are rejected there
+/// rather than wherever they end up being used.
+pub trait Definition: Asset + DeserializeOwned {
+    fn validate(&self) -> Result<(), BevyError> {
+        Ok(())
+    }
+}
+
+/// Loads a designer-authored definition asset from a RON file.
+pub struct RonDefinitionLoader<A> {
+    extensions: &'static [&'static str],
+    _definition: PhantomData<fn() -> A>,
+}
+
+impl<A> RonDefinitionLoader<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            _definition: PhantomData,
+        }
+    }
+}
+
+impl<A: Definition> AssetLoader for RonDefinitionLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let definition: A = ron::de::from_bytes(&bytes)?;
+        definition.validate()?;
+
+        Ok(definition)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -1,24 +1,44 @@
-use bevy::{
-    app::{App, Startup},
-    ecs::{component::Component, system::Commands},
-    light::SpotLight,
-    math::Vec3,
-    transform::components::Transform,
-};
-
-#[derive(Component)]
-pub struct Light;
-
-pub(crate) fn plugin(app: &mut App) {
-    app.add_systems(Startup, spawn_light);
-}
-
-fn spawn_light(mut commands: Commands) {
-    commands.spawn((
-        Light,
-        SpotLight {
-            ..Default::default()
-        },
-        Transform::from_xyz(0., 15., 0.).looking_at(Vec3::ZERO, Vec3::Y),
-    ));
-}
+use bevy::{
+    app::{App, Startup, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Added,
+        system::{Commands, Query},
+    },
+    light::{PointLight, SpotLight},
+    math::Vec3,
+    transform::components::Transform,
+};
+
+use crate::maps::MapLight;
+
+#[derive(Component)]
+pub struct Light;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(Startup, spawn_light)
+        .add_systems(Update, light_map_markers);
+}
+
+fn spawn_light(mut commands: Commands) {
+    commands.spawn((
+        Light,
+        SpotLight {
+            ..Default::default()
+        },
+        Transform::from_xyz(0., 15., 0.).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+fn light_map_markers(mut commands: Commands, map_lights: Query<Entity, Added<MapLight>>) {
+    for map_light in map_lights.iter() {
+        commands.entity(map_light).insert((
+            Light,
+            PointLight {
+                shadows_enabled: true,
+                ..Default::default()
+            },
+        ));
+    }
+}
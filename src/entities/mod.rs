@@ -1,4 +1,5 @@
-pub(crate) mod bullet;
-pub(crate) mod lights;
-pub(crate) mod tank_body;
-pub(crate) mod turret;
+pub(crate) mod bullet;
+pub(crate) mod definition;
+pub(crate) mod lights;
+pub(crate) mod tank_body;
+pub(crate) mod turret;
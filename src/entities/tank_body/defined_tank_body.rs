@@ -0,0 +1,90 @@
+use avian3d::prelude::{CoefficientCombine, Collider, Friction, Mass, RigidBody};
+use bevy::{
+    app::{App, FixedUpdate},
+    asset::{AssetPath, AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Without,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, EntityCommands, Query, Res},
+    },
+    scene::SceneRoot,
+};
+
+use crate::{
+    entities::tank_body::{
+        TankBody, TankBodySpawner, TankHandling, TurretMounts, definition::TankBodyDefinition,
+    },
+    tick::GameplaySystems,
+};
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        build_defined_tank_bodies.in_set(GameplaySystems::Simulate),
+    );
+}
+
+trait DefinedTankBodySpawner {
+    fn spawn_defined_tank_body<'a>(
+        &'a mut self,
+        definition: Handle<TankBodyDefinition>,
+    ) -> EntityCommands<'a>;
+}
+
+/// A tank body built entirely from a [`TankBodyDefinition`] asset.
+#[derive(Component)]
+#[require(TankBody)]
+pub struct DefinedTankBody {
+    pub definition: Handle<TankBodyDefinition>,
+}
+
+/// Builds a [`DefinedTankBody`] from the definition at `path`.
+pub struct TankBodyFromDefinition {
+    pub path: AssetPath<'static>,
+}
+
+impl TankBodySpawner for TankBodyFromDefinition {
+    fn spawn<'a>(
+        &self,
+        commands: &'a mut Commands,
+        asset_server: &AssetServer,
+    ) -> EntityCommands<'a> {
+        commands.spawn_defined_tank_body(asset_server.load(self.path.clone()))
+    }
+}
+
+impl DefinedTankBodySpawner for Commands<'_, '_> {
+    fn spawn_defined_tank_body(
+        &mut self,
+        definition: Handle<TankBodyDefinition>,
+    ) -> EntityCommands<'_> {
+        self.spawn(DefinedTankBody { definition })
+    }
+}
+
+/// Fills in a [`DefinedTankBody`] once its definition has loaded. The body only becomes a
+/// rigid body at that point, so it can't fall through the map while it has no collider.
+fn build_defined_tank_bodies(
+    mut commands: Commands,
+    tank_bodies: Query<(Entity, &DefinedTankBody), Without<TankHandling>>,
+    tank_body_definitions: Res<Assets<TankBodyDefinition>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, defined_tank_body) in tank_bodies.iter() {
+        let Some(definition) = tank_body_definitions.get(&defined_tank_body.definition) else {
+            continue;
+        };
+
+        commands.entity(entity).insert((
+            RigidBody::Dynamic,
+            Collider::from(&definition.collider),
+            Mass(definition.mass),
+            Friction::new(definition.friction).with_combine_rule(CoefficientCombine::Min),
+            definition.handling.clone(),
+            TurretMounts(definition.turret_mounts.clone()),
+            SceneRoot(asset_server.load(&definition.model)),
+        ));
+    }
+}
@@ -0,0 +1,42 @@
+use avian3d::prelude::Collider;
+use bevy::{asset::Asset, math::Vec3, reflect::TypePath};
+use serde::Deserialize;
+
+use crate::entities::{definition::Definition, tank_body::TankHandling};
+
+/// Everything that makes one kind of tank body different from another, loaded from a
+/// `.tank_body.ron` file.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct TankBodyDefinition {
+    /// Path of the glTF scene to show for the body.
+    pub model: String,
+    pub collider: ColliderShape,
+    pub mass: f32,
+    /// Friction against whatever the body touches. The tracks' own grip comes from
+    /// `handling`, so this is usually kept low.
+    pub friction: f32,
+    pub handling: TankHandling,
+    /// Where turrets sit on the body, in the order the tank's turrets were spawned.
+    pub turret_mounts: Vec<Vec3>,
+}
+
+impl Definition for TankBodyDefinition {}
+
+#[derive(Deserialize)]
+pub enum ColliderShape {
+    Cuboid { x: f32, y: f32, z: f32 },
+    Cylinder { radius: f32, height: f32 },
+    Capsule { radius: f32, length: f32 },
+    Sphere { radius: f32 },
+}
+
+impl From<&ColliderShape> for Collider {
+    fn from(shape: &ColliderShape) -> Self {
+        match *shape {
+            ColliderShape::Cuboid { x, y, z } => Collider::cuboid(x, y, z),
+            ColliderShape::Cylinder { radius, height } => Collider::cylinder(radius, height),
+            ColliderShape::Capsule { radius, length } => Collider::capsule(radius, length),
+            ColliderShape::Sphere { radius } => Collider::sphere(radius),
+        }
+    }
+}
@@ -1,77 +1,208 @@
-pub(crate) mod basic_tank_body;
-
-use avian3d::prelude::{AngularVelocity, LinearVelocity};
-use bevy::{
-    app::{App, Update},
-    asset::AssetServer,
-    ecs::{
-        component::Component,
-        entity::Entity,
-        message::{Message, MessageReader},
-        query::With,
-        system::{Commands, EntityCommands, Query, Res},
-    },
-    time::Time,
-    transform::components::Transform,
-};
-
-pub fn plugin(app: &mut App) {
-    app.add_message::<Movement>().add_systems(Update, move_tank);
-}
-
-const LINEAR_MOVEMENT_SPEED: f32 = 10.;
-const ANGULAR_MOVEMENT_SPEED: f32 = 50.;
-
-pub trait TankBodySpawner {
-    fn spawn<'a>(
-        &self,
-        commands: &'a mut Commands,
-        asset_server: &AssetServer,
-    ) -> EntityCommands<'a>;
-}
-
-#[derive(Message)]
-pub struct Movement {
-    pub entity: Entity,
-    pub movement_type: MovementType,
-}
-
-pub enum MovementType {
-    Linear(i8),
-    Angular(i8),
-}
-
-#[derive(Component, Default)]
-#[require(Transform::from_xyz(0., 0.5, 0.))]
-pub struct TankBody;
-
-fn move_tank(
-    mut movement_event_reader: MessageReader<Movement>,
-    mut tanks: Query<(&mut LinearVelocity, &mut AngularVelocity, &Transform), With<TankBody>>,
-    time: Res<Time>,
-) {
-    let delta_time = time.delta_secs();
-
-    for event in movement_event_reader.read() {
-        if let Ok((mut linear_velocity, mut angular_velocity, transform)) =
-            tanks.get_mut(event.entity)
-        {
-            match event.movement_type {
-                MovementType::Linear(linear_amount) => {
-                    linear_velocity.z += transform.forward().z
-                        * (linear_amount as f32)
-                        * delta_time
-                        * LINEAR_MOVEMENT_SPEED;
-                    linear_velocity.x += transform.forward().x
-                        * (linear_amount as f32)
-                        * delta_time
-                        * LINEAR_MOVEMENT_SPEED;
-                }
-                MovementType::Angular(angular_amount) => {
-                    angular_velocity.y +=
-                        angular_amount as f32 * delta_time * ANGULAR_MOVEMENT_SPEED;
-                }
-            }
-        }
-    }
-}
+pub(crate) mod defined_tank_body;
+pub(crate) mod definition;
+
+use avian3d::prelude::{AngularVelocity, LinearVelocity};
+use bevy::{
+    app::{App, FixedUpdate},
+    asset::{AssetApp, AssetServer},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::Children,
+        message::{Message, MessageReader},
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, EntityCommands, Query, Res},
+    },
+    math::Vec3,
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    entities::{definition::RonDefinitionLoader, turret::Turret},
+    tick::GameplaySystems,
+};
+
+use definition::TankBodyDefinition;
+use serde::Deserialize;
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(defined_tank_body::plugin)
+        .init_asset::<TankBodyDefinition>()
+        .register_asset_loader(RonDefinitionLoader::<TankBodyDefinition>::new(&[
+            "tank_body.ron",
+        ]))
+        .add_message::<Movement>()
+        .add_systems(
+            FixedUpdate,
+            (control_tracks, drive_tracks, mount_turrets)
+                .chain()
+                .in_set(GameplaySystems::Simulate),
+        );
+}
+
+pub trait TankBodySpawner {
+    fn spawn<'a>(
+        &self,
+        commands: &'a mut Commands,
+        asset_server: &AssetServer,
+    ) -> EntityCommands<'a>;
+}
+
+#[derive(Message)]
+pub struct Movement {
+    pub entity: Entity,
+    pub movement_type: MovementType,
+}
+
+pub enum MovementType {
+    /// From -1 for full reverse to 1 for full ahead.
+    Throttle(f32),
+    /// From -1 for a full right turn to 1 for a full left turn.
+    Steer(f32),
+}
+
+#[derive(Component, Default)]
+#[require(Transform::from_xyz(0., 0.5, 0.), TrackControls)]
+pub struct TankBody;
+
+/// The throttle and steering a tank's tracks are currently driven with.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct TrackControls {
+    pub throttle: f32,
+    pub steer: f32,
+}
+
+/// How a tank body drives, taken from its definition. Speeds are in metres per second and
+/// rates of change in metres per second squared, turning in radians.
+#[derive(Component, Deserialize, Clone, Debug)]
+pub struct TankHandling {
+    pub max_speed: f32,
+    pub max_reverse_speed: f32,
+    /// Acceleration from standstill. It falls off towards nothing at top speed.
+    pub acceleration: f32,
+    /// Deceleration while the throttle is against the direction of travel.
+    pub braking: f32,
+    /// Deceleration while coasting.
+    pub rolling_drag: f32,
+    /// How quickly sideways drift is cancelled, as a fraction per second.
+    pub lateral_grip: f32,
+    /// Fastest turn at top speed.
+    pub turn_rate: f32,
+    /// Fastest turn on the spot, with the tracks running in opposite directions.
+    pub pivot_turn_rate: f32,
+    pub turn_acceleration: f32,
+}
+
+/// Where a tank body's turrets sit, in the order its turrets were spawned.
+#[derive(Component)]
+pub struct TurretMounts(pub Vec<Vec3>);
+
+fn control_tracks(
+    mut movement_event_reader: MessageReader<Movement>,
+    mut tanks: Query<&mut TrackControls>,
+) {
+    for event in movement_event_reader.read() {
+        if let Ok(mut track_controls) = tanks.get_mut(event.entity) {
+            match event.movement_type {
+                MovementType::Throttle(throttle) => {
+                    track_controls.throttle = throttle.clamp(-1., 1.)
+                }
+                MovementType::Steer(steer) => track_controls.steer = steer.clamp(-1., 1.),
+            }
+        }
+    }
+}
+
+/// Drives each tank like a tracked vehicle. Only the velocity along the tracks and the spin
+/// about the tank's up axis are driven, so gravity and collisions still act on everything else.
+fn drive_tracks(
+    mut tanks: Query<
+        (
+            &mut LinearVelocity,
+            &mut AngularVelocity,
+            &Transform,
+            &TrackControls,
+            &TankHandling,
+        ),
+        With<TankBody>,
+    >,
+    time: Res<Time>,
+) {
+    let delta_time = time.delta_secs();
+
+    for (mut linear_velocity, mut angular_velocity, transform, controls, handling) in
+        tanks.iter_mut()
+    {
+        let forward = transform.forward().as_vec3();
+        let right = transform.right().as_vec3();
+        let up = transform.up().as_vec3();
+
+        let speed = linear_velocity.dot(forward);
+        let lateral_speed = linear_velocity.dot(right);
+        let vertical_speed = linear_velocity.dot(up);
+
+        let new_speed = if controls.throttle != 0. && speed * controls.throttle >= 0. {
+            let top_speed = if controls.throttle > 0. {
+                handling.max_speed
+            } else {
+                handling.max_reverse_speed
+            };
+            let headroom = (1. - speed.abs() / top_speed).max(0.);
+
+            speed + controls.throttle * handling.acceleration * headroom * delta_time
+        } else if controls.throttle != 0. {
+            approach_zero(
+                speed,
+                handling.braking * controls.throttle.abs() * delta_time,
+            )
+        } else {
+            approach_zero(speed, handling.rolling_drag * delta_time)
+        };
+
+        let new_lateral_speed = lateral_speed * (-handling.lateral_grip * delta_time).exp();
+
+        linear_velocity.0 = forward * new_speed + right * new_lateral_speed + up * vertical_speed;
+
+        // Tracks turn quickest on the spot and slow down their turns as the tank picks up speed.
+        let speed_fraction = (new_speed.abs() / handling.max_speed).min(1.);
+        let turn_limit = handling.pivot_turn_rate
+            + (handling.turn_rate - handling.pivot_turn_rate) * speed_fraction;
+
+        let yaw_rate = angular_velocity.dot(up);
+        let target_yaw_rate = controls.steer * turn_limit;
+        let max_change = handling.turn_acceleration * delta_time;
+        let new_yaw_rate = yaw_rate + (target_yaw_rate - yaw_rate).clamp(-max_change, max_change);
+
+        angular_velocity.0 += up * (new_yaw_rate - yaw_rate);
+    }
+}
+
+/// Moves `value` towards zero by at most `amount`, without overshooting.
+fn approach_zero(value: f32, amount: f32) -> f32 {
+    value.signum() * (value.abs() - amount).max(0.)
+}
+
+/// Moves each tank's turrets onto the body's mount points, once both have been built.
+fn mount_turrets(
+    tank_bodies: Query<(&TurretMounts, &Children)>,
+    mut turrets: Query<&mut Transform, With<Turret>>,
+) {
+    for (turret_mounts, children) in tank_bodies.iter() {
+        let mounted_turrets: Vec<Entity> = children
+            .iter()
+            .copied()
+            .filter(|&c| turrets.contains(c))
+            .take(turret_mounts.0.len())
+            .collect();
+
+        for (turret, mount) in mounted_turrets.into_iter().zip(turret_mounts.0.iter()) {
+            if let Ok(mut transform) = turrets.get_mut(turret)
+                && transform.translation != *mount
+            {
+                transform.translation = *mount;
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use avian3d::prelude::{Collider, CollisionEventsEnabled, LinearVelocity, RigidBody};
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate},
+    asset::{AssetPath, AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::{ChildOf, Children},
+        message::{MessageReader, MessageWriter},
+        query::{With, Without},
+        relationship::RelatedSpawnerCommands,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::primitives::Sphere,
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    scene::SceneRoot,
+    state::state_scoped::DespawnOnExit,
+    time::{Time, Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    combat::Shooter,
+    entities::{
+        bullet::{Bullet, Projectile},
+        turret::{
+            BulletSpawner, DryFire, Shoot, Turret, TurretSpawner, WeaponState,
+            definition::{FireMode, TurretDefinition},
+        },
+    },
+    state::GameState,
+    tank::PlayerId,
+    team::Team,
+    tick::GameplaySystems,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (build_defined_turrets, tick_weapons, shoot_bullet)
+            .chain()
+            .in_set(GameplaySystems::Simulate),
+    );
+}
+
+trait DefinedTurretSpawner {
+    fn spawn_defined_turret(&mut self, definition: Handle<TurretDefinition>);
+}
+
+/// A turret built entirely from a [`TurretDefinition`] asset.
+#[derive(Component)]
+pub struct DefinedTurret {
+    pub definition: Handle<TurretDefinition>,
+}
+
+/// Builds a [`DefinedTurret`] from the definition at `path`.
+pub struct TurretFromDefinition {
+    pub path: AssetPath<'static>,
+}
+
+impl TurretSpawner for TurretFromDefinition {
+    fn spawn_turret(
+        &self,
+        commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
+        asset_server: &AssetServer,
+    ) {
+        commands.spawn_defined_turret(asset_server.load(self.path.clone()));
+    }
+}
+
+impl DefinedTurretSpawner for RelatedSpawnerCommands<'_, ChildOf> {
+    fn spawn_defined_turret(&mut self, definition: Handle<TurretDefinition>) {
+        self.spawn(DefinedTurret { definition });
+    }
+}
+
+/// Fills in a [`DefinedTurret`] once its definition has loaded. Until then it has no
+/// [`Turret`], so nothing can aim or fire it.
+fn build_defined_turrets(
+    mut commands: Commands,
+    turrets: Query<(Entity, &DefinedTurret), Without<Turret>>,
+    turret_definitions: Res<Assets<TurretDefinition>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, defined_turret) in turrets.iter() {
+        let Some(definition) = turret_definitions.get(&defined_turret.definition) else {
+            continue;
+        };
+
+        commands
+            .entity(entity)
+            .insert((
+                Turret {
+                    rotation_speed: definition.rotation_speed,
+                },
+                WeaponState::new(
+                    definition.fire_mode,
+                    Duration::from_secs_f32(1. / definition.fire_rate),
+                    definition.magazine_size,
+                    Duration::from_secs_f32(definition.reload_time),
+                ),
+                SceneRoot(asset_server.load(&definition.model)),
+            ))
+            .with_children(|parent| {
+                for muzzle in definition.muzzles.iter() {
+                    parent.spawn((
+                        BulletSpawner,
+                        RigidBody::Kinematic,
+                        Transform::from_translation(muzzle.translation)
+                            .with_rotation(muzzle.rotation),
+                    ));
+                }
+            });
+    }
+}
+
+fn tick_weapons(mut weapons: Query<&mut WeaponState>, time: Res<Time>) {
+    for mut weapon in weapons.iter_mut() {
+        weapon.tick(time.delta());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn shoot_bullet(
+    mut shoot_event_reader: MessageReader<Shoot>,
+    mut dry_fire_event_writer: MessageWriter<DryFire>,
+    mut weapons: Query<(Entity, &mut WeaponState)>,
+    turrets: Query<(&DefinedTurret, &Children, &ChildOf)>,
+    turret_definitions: Res<Assets<TurretDefinition>>,
+    tanks: Query<(&Team, &PlayerId)>,
+    bullet_spawner: Query<&GlobalTransform, With<BulletSpawner>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let mut firing_turrets = Vec::new();
+
+    for event in shoot_event_reader.read() {
+        let Ok((_, mut weapon)) = weapons.get_mut(event.turret) else {
+            continue;
+        };
+
+        if event.held && weapon.fire_mode != FireMode::Auto {
+            continue;
+        }
+
+        if let Some(reason) = weapon.dry_fire_reason() {
+            if !event.held {
+                dry_fire_event_writer.write(DryFire {
+                    turret: event.turret,
+                    reason,
+                });
+            }
+            continue;
+        }
+
+        if let FireMode::Burst(shots) = weapon.fire_mode {
+            weapon.burst_remaining = shots;
+        }
+
+        firing_turrets.push(event.turret);
+    }
+
+    // Bursts carry on by themselves once started, one shot per fire interval.
+    for (turret, weapon) in weapons.iter() {
+        if weapon.burst_remaining > 0 && weapon.can_fire() && !firing_turrets.contains(&turret) {
+            firing_turrets.push(turret);
+        }
+    }
+
+    for turret in firing_turrets {
+        if let Ok((_, mut weapon)) = weapons.get_mut(turret)
+            && let Ok((defined_turret, turret_children, turret_parent)) = turrets.get(turret)
+            && let Some(definition) = turret_definitions.get(&defined_turret.definition)
+            && let Ok((team, player)) = tanks.get(turret_parent.parent())
+        {
+            weapon.burst_remaining = weapon.burst_remaining.saturating_sub(1);
+            weapon.fire();
+
+            let shooter = Shooter {
+                tank: turret_parent.parent(),
+                player: *player,
+                weapon: defined_turret.definition.clone(),
+            };
+
+            let projectile = &definition.projectile;
+            let bullet = meshes.add(Sphere::new(projectile.radius));
+            let bullet_material = materials.add(StandardMaterial {
+                base_color: team.color,
+                ..Default::default()
+            });
+
+            for spawner_transform in turret_children
+                .into_iter()
+                .filter_map(|t| bullet_spawner.get(*t).ok())
+            {
+                commands.spawn((
+                    Bullet::new(projectile.damage, shooter.clone(), team),
+                    Projectile {
+                        lifetime: Timer::from_seconds(projectile.lifetime, TimerMode::Once),
+                        origin: spawner_transform.translation(),
+                        max_distance: projectile.max_distance,
+                        despawn_on_impact: projectile.despawn_on_impact,
+                    },
+                    Mesh3d(bullet.clone()),
+                    MeshMaterial3d(bullet_material.clone()),
+                    Transform::from(*spawner_transform),
+                    RigidBody::Dynamic,
+                    Collider::sphere(projectile.radius),
+                    CollisionEventsEnabled,
+                    LinearVelocity(spawner_transform.forward() * projectile.speed),
+                    DespawnOnExit(GameState::InMatch),
+                ));
+            }
+        }
+    }
+}
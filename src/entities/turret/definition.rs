@@ -0,0 +1,150 @@
+use bevy::{
+    asset::Asset,
+    ecs::error::BevyError,
+    math::{Quat, Vec3},
+    reflect::TypePath,
+};
+use serde::Deserialize;
+
+use crate::entities::{bullet::DespawnOnImpact, definition::Definition};
+
+/// Everything that makes one kind of turret different from another, loaded from a
+/// `.turret.ron` file.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct TurretDefinition {
+    /// Path of the glTF scene to show for the turret.
+    pub model: String,
+    /// Where bullets leave the turret, relative to it. Every muzzle fires on each shot.
+    pub muzzles: Vec<MuzzleDefinition>,
+    pub projectile: ProjectileDefinition,
+    /// Shots per second.
+    pub fire_rate: f32,
+    /// Shots fired before the turret has to reload. At least 1.
+    pub magazine_size: u32,
+    /// Seconds it takes to refill an empty magazine.
+    pub reload_time: f32,
+    #[serde(default)]
+    pub fire_mode: FireMode,
+    /// At least 1.
+    pub rotation_speed: f32,
+}
+
+impl Definition for TurretDefinition {
+    fn validate(&self) -> Result<(), BevyError> {
+        if !(self.fire_rate.is_finite() && self.fire_rate > 0.) {
+            return Err(format!("fire_rate must be above 0, not {}", self.fire_rate).into());
+        }
+        if self.magazine_size == 0 {
+            return Err("magazine_size must be at least 1".into());
+        }
+        if self.fire_mode == FireMode::Burst(0) {
+            return Err("a Burst fire_mode must fire at least 1 shot".into());
+        }
+        if !(self.reload_time.is_finite() && self.reload_time >= 0.) {
+            return Err(format!("reload_time can't be negative, not {}", self.reload_time).into());
+        }
+        if !(self.projectile.lifetime.is_finite() && self.projectile.lifetime >= 0.) {
+            return Err(format!(
+                "projectile lifetime can't be negative, not {}",
+                self.projectile.lifetime
+            )
+            .into());
+        }
+        if !(self.rotation_speed.is_finite() && self.rotation_speed >= 1.) {
+            return Err(format!(
+                "rotation_speed must be at least 1, not {}",
+                self.rotation_speed
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// What pulling the trigger does.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FireMode {
+    /// One shot per press.
+    #[default]
+    Semi,
+    /// Keeps firing for as long as the trigger is held.
+    Auto,
+    /// A fixed number of shots per press, at least 1.
+    Burst(u32),
+}
+
+#[derive(Deserialize)]
+pub struct MuzzleDefinition {
+    pub translation: Vec3,
+    #[serde(default)]
+    pub rotation: Quat,
+}
+
+#[derive(Deserialize)]
+pub struct ProjectileDefinition {
+    pub speed: f32,
+    pub radius: f32,
+    pub damage: u8,
+    /// Seconds before the projectile is removed, whatever it has or hasn't hit.
+    pub lifetime: f32,
+    /// Distance from the muzzle after which the projectile is removed.
+    pub max_distance: f32,
+    #[serde(default)]
+    pub despawn_on_impact: DespawnOnImpact,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn basic() -> TurretDefinition {
+        ron::from_str(include_str!("../../../assets/turrets/basic.turret.ron")).unwrap()
+    }
+
+    #[test]
+    fn basic_turret_is_valid() {
+        assert!(basic().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_values_that_would_panic() {
+        for fire_rate in [0., -1., f32::NAN, f32::INFINITY] {
+            let definition = TurretDefinition {
+                fire_rate,
+                ..basic()
+            };
+            assert!(definition.validate().is_err(), "fire_rate {fire_rate}");
+        }
+
+        for rotation_speed in [0.5, f32::NAN] {
+            let definition = TurretDefinition {
+                rotation_speed,
+                ..basic()
+            };
+            assert!(
+                definition.validate().is_err(),
+                "rotation_speed {rotation_speed}"
+            );
+        }
+
+        let definition = TurretDefinition {
+            reload_time: -1.,
+            ..basic()
+        };
+        assert!(definition.validate().is_err());
+
+        // Would start empty and never get to reload, dry-firing forever.
+        let definition = TurretDefinition {
+            magazine_size: 0,
+            ..basic()
+        };
+        assert!(definition.validate().is_err());
+
+        let definition = TurretDefinition {
+            fire_mode: FireMode::Burst(0),
+            ..basic()
+        };
+        assert!(definition.validate().is_err());
+    }
+}
@@ -1,90 +1,201 @@
-pub(crate) mod basic_turret;
-
-use avian3d::math::PI;
-use bevy::{
-    app::{App, Update},
-    asset::AssetServer,
-    ecs::{
-        component::Component,
-        entity::Entity,
-        hierarchy::ChildOf,
-        message::{Message, MessageReader},
-        query::With,
-        relationship::RelatedSpawnerCommands,
-        system::{Query, Res},
-    },
-    math::{Vec3, Vec3Swizzles, ops::acos},
-    transform::components::{GlobalTransform, Transform},
-};
-
-pub fn plugin(app: &mut App) {
-    app.add_plugins(basic_turret::plugin)
-        .add_message::<TurretMovement>()
-        .add_message::<Shoot>()
-        .add_systems(Update, move_turret);
-}
-
-const TURRET_ROTATION_SPEED: f32 = 3.;
-
-pub trait TurretSpawner {
-    fn spawn_turret(
-        &self,
-        commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
-        asset_server: &AssetServer,
-    );
-}
-
-#[derive(Component)]
-pub struct BulletSpawner;
-
-#[derive(Message)]
-pub struct Shoot {
-    pub turret: Entity,
-}
-
-#[derive(Message)]
-pub struct TurretMovement {
-    pub turret_entity: Entity,
-    pub x: f32,
-    pub z: f32,
-}
-
-#[derive(Component, Default)]
-pub struct Turret;
-
-fn move_turret(
-    mut turret_movement_event_reader: MessageReader<TurretMovement>,
-    mut turret_transforms: Query<(&mut Transform, &GlobalTransform), With<Turret>>,
-    time: Res<bevy::time::Time>,
-) {
-    for event in turret_movement_event_reader.read() {
-        if let Ok((mut turret_transform, turret_global_transform)) =
-            turret_transforms.get_mut(event.turret_entity)
-        {
-            let turret_translation = turret_transform.translation.clone();
-
-            let x = event.x;
-            let y = turret_translation.y;
-            let z = event.z;
-
-            let to_cursor = (Vec3::new(x, y, z) - turret_translation).normalize();
-
-            let _turret_rotation = turret_transform.rotation.clone();
-
-            let turret_rotation_x = (turret_global_transform.rotation() * Vec3::X).normalize();
-            let turret_rotation_y = turret_global_transform.forward().normalize();
-
-            let rotation_angle = acos(turret_rotation_y.xz().dot(to_cursor.xz()).clamp(-1., 1.));
-
-            if rotation_angle - (PI / 180.) > f32::EPSILON {
-                let rotation_sign = -f32::copysign(1., turret_rotation_x.dot(to_cursor));
-
-                let turret_rotation_rate: f32 =
-                    (TURRET_ROTATION_SPEED / rotation_angle).clamp(1., TURRET_ROTATION_SPEED);
-                turret_transform.rotate_y(
-                    rotation_sign * rotation_angle * turret_rotation_rate * time.delta_secs(),
-                );
-            }
-        }
-    }
-}
+pub(crate) mod defined_turret;
+pub(crate) mod definition;
+
+use avian3d::math::PI;
+use bevy::{
+    app::{App, FixedUpdate, Update},
+    asset::{AssetApp, AssetServer},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        message::{Message, MessageReader},
+        relationship::RelatedSpawnerCommands,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res},
+    },
+    log::debug,
+    math::{Vec3, Vec3Swizzles, ops::acos},
+    time::{Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+};
+use std::time::Duration;
+
+use crate::{entities::definition::RonDefinitionLoader, tick::GameplaySystems};
+
+use definition::{FireMode, TurretDefinition};
+
+pub fn plugin(app: &mut App) {
+    app.add_plugins(defined_turret::plugin)
+        .init_asset::<TurretDefinition>()
+        .register_asset_loader(RonDefinitionLoader::<TurretDefinition>::new(&[
+            "turret.ron",
+        ]))
+        .add_message::<TurretMovement>()
+        .add_message::<Shoot>()
+        .add_message::<DryFire>()
+        .add_systems(FixedUpdate, move_turret.in_set(GameplaySystems::Simulate))
+        .add_systems(Update, log_dry_fire);
+}
+
+pub trait TurretSpawner {
+    fn spawn_turret(
+        &self,
+        commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
+        asset_server: &AssetServer,
+    );
+}
+
+#[derive(Component)]
+pub struct BulletSpawner;
+
+#[derive(Message)]
+pub struct Shoot {
+    pub turret: Entity,
+    /// The trigger was already down on the previous tick. Only automatic weapons keep firing
+    /// while it's held.
+    pub held: bool,
+}
+
+/// A fresh pull of the trigger that the turret refused.
+#[derive(Message)]
+pub struct DryFire {
+    pub turret: Entity,
+    pub reason: DryFireReason,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DryFireReason {
+    CoolingDown,
+    Reloading,
+}
+
+#[derive(Message)]
+pub struct TurretMovement {
+    pub turret_entity: Entity,
+    pub x: f32,
+    pub z: f32,
+}
+
+#[derive(Component)]
+pub struct Turret {
+    pub rotation_speed: f32,
+}
+
+/// Rate of fire, magazine and reload of a [`Turret`].
+#[derive(Component)]
+pub struct WeaponState {
+    pub fire_mode: FireMode,
+    /// Time left until the next shot may be fired.
+    pub cooldown: Timer,
+    pub magazine_size: u32,
+    pub rounds: u32,
+    /// Starts as soon as the magazine runs dry and refills it when it finishes.
+    pub reload: Timer,
+    /// Shots still to come from the current burst.
+    pub burst_remaining: u32,
+}
+
+impl WeaponState {
+    pub fn new(
+        fire_mode: FireMode,
+        fire_interval: Duration,
+        magazine_size: u32,
+        reload_time: Duration,
+    ) -> Self {
+        let mut cooldown = Timer::new(fire_interval, TimerMode::Once);
+        cooldown.set_elapsed(fire_interval);
+        let mut reload = Timer::new(reload_time, TimerMode::Once);
+        reload.set_elapsed(reload_time);
+
+        Self {
+            fire_mode,
+            cooldown,
+            magazine_size,
+            rounds: magazine_size,
+            reload,
+            burst_remaining: 0,
+        }
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        !self.reload.is_finished()
+    }
+
+    pub fn can_fire(&self) -> bool {
+        self.cooldown.is_finished() && !self.is_reloading() && self.rounds > 0
+    }
+
+    pub fn dry_fire_reason(&self) -> Option<DryFireReason> {
+        if self.is_reloading() || self.rounds == 0 {
+            Some(DryFireReason::Reloading)
+        } else if !self.cooldown.is_finished() {
+            Some(DryFireReason::CoolingDown)
+        } else {
+            None
+        }
+    }
+
+    /// Spends a round, starting a reload when it was the last one.
+    fn fire(&mut self) {
+        self.cooldown.reset();
+        self.rounds = self.rounds.saturating_sub(1);
+
+        if self.rounds == 0 {
+            self.burst_remaining = 0;
+            self.reload.reset();
+        }
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        self.cooldown.tick(delta);
+
+        if self.reload.tick(delta).just_finished() {
+            self.rounds = self.magazine_size;
+        }
+    }
+}
+
+fn move_turret(
+    mut turret_movement_event_reader: MessageReader<TurretMovement>,
+    mut turret_transforms: Query<(&mut Transform, &GlobalTransform, &Turret)>,
+    time: Res<bevy::time::Time>,
+) {
+    for event in turret_movement_event_reader.read() {
+        if let Ok((mut turret_transform, turret_global_transform, turret)) =
+            turret_transforms.get_mut(event.turret_entity)
+        {
+            let turret_translation = turret_global_transform.translation();
+
+            let x = event.x;
+            let y = turret_translation.y;
+            let z = event.z;
+
+            let to_cursor = (Vec3::new(x, y, z) - turret_translation).normalize();
+
+            let _turret_rotation = turret_transform.rotation;
+
+            let turret_rotation_x = (turret_global_transform.rotation() * Vec3::X).normalize();
+            let turret_rotation_y = turret_global_transform.forward().normalize();
+
+            let rotation_angle = acos(turret_rotation_y.xz().dot(to_cursor.xz()).clamp(-1., 1.));
+
+            if rotation_angle - (PI / 180.) > f32::EPSILON {
+                let rotation_sign = -f32::copysign(1., turret_rotation_x.dot(to_cursor));
+
+                let turret_rotation_rate: f32 = (turret.rotation_speed / rotation_angle)
+                    .min(turret.rotation_speed)
+                    .max(1.);
+                turret_transform.rotate_y(
+                    rotation_sign * rotation_angle * turret_rotation_rate * time.delta_secs(),
+                );
+            }
+        }
+    }
+}
+
+fn log_dry_fire(mut dry_fire_event_reader: MessageReader<DryFire>) {
+    for event in dry_fire_event_reader.read() {
+        debug!("Turret {} dry fired: {:?}", event.turret, event.reason);
+    }
+}
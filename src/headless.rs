@@ -0,0 +1,43 @@
+use bevy::{
+    app::App,
+    asset::{AssetApp, AssetPlugin},
+    gltf::GltfPlugin,
+    image::{CompressedImageFormatSupport, CompressedImageFormats, ImagePlugin},
+    mesh::MeshPlugin,
+    pbr::{MeshMaterial3d, StandardMaterial},
+    scene::ScenePlugin,
+    transform::TransformPlugin,
+};
+
+use crate::{
+    state::{MatchSettings, SkipMenus},
+    tank::Controller,
+};
+
+/// The asset and scene plugins the simulation needs when there is no `DefaultPlugins`.
+///
+/// Materials are registered without the renderer so glTF scenes and bullets can still be
+/// spawned, they just never get drawn.
+///
+/// With no one to pick from the menus or drive a tank, matches start on their own and every
+/// tank is left to the AI.
+pub(super) fn plugin(app: &mut App) {
+    let mut match_settings = MatchSettings::default();
+    for match_player in match_settings.players.iter_mut() {
+        match_player.controller = Controller::Program;
+    }
+
+    app.insert_resource(CompressedImageFormatSupport(CompressedImageFormats::NONE))
+        .add_plugins((
+            TransformPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+            ImagePlugin::default(),
+            MeshPlugin,
+            GltfPlugin::default(),
+        ))
+        .init_asset::<StandardMaterial>()
+        .register_type::<MeshMaterial3d<StandardMaterial>>()
+        .insert_resource(SkipMenus(true))
+        .insert_resource(match_settings);
+}
@@ -0,0 +1,578 @@
+use std::path::Path;
+
+use bevy::{
+    app::{App, Startup, Update},
+    ecs::{
+        entity::Entity,
+        error::BevyError,
+        message::{Message, MessageReader, MessageWriter},
+        query::Added,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemSet},
+        system::{Commands, Query, Res, ResMut, SystemParam},
+    },
+    input::{
+        ButtonInput,
+        gamepad::{Gamepad, GamepadButton},
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+    log::{info, warn},
+    math::Vec2,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::tank::LocalPlayer;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InputBindings>()
+        .init_resource::<GamepadOrder>()
+        .add_message::<RebindAction>()
+        .add_message::<SaveInputBindings>()
+        .add_systems(Startup, load_input_bindings)
+        .add_systems(
+            Update,
+            // A rebind only listens from the frame after it starts, so the press that asked
+            // for it isn't taken as the new binding.
+            (
+                record_gamepads,
+                capture_rebind,
+                start_rebind,
+                save_input_bindings,
+            )
+                .chain()
+                .in_set(RebindSystems),
+        );
+}
+
+/// Records gamepads and carries out rebinds. Menus that start or show rebinds go before it.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RebindSystems;
+
+/// Where bindings are read from at startup and written back to when they change.
+pub const INPUT_CONFIG_PATH: &str = "config/input.ron";
+
+/// The devices one player's bindings are read from this frame.
+pub struct Devices<'a> {
+    pub keys: &'a ButtonInput<KeyCode>,
+    pub mouse: &'a ButtonInput<MouseButton>,
+    /// The player's gamepad, if they have one and it's connected.
+    pub gamepad: Option<&'a Gamepad>,
+}
+
+impl<'a> Devices<'a> {
+    /// The devices `player_bindings` are read from.
+    pub fn of(
+        player_bindings: &PlayerBindings,
+        keys: &'a ButtonInput<KeyCode>,
+        mouse: &'a ButtonInput<MouseButton>,
+        gamepads: &'a PlayerGamepads,
+    ) -> Self {
+        Self {
+            keys,
+            mouse,
+            gamepad: player_bindings
+                .gamepad
+                .and_then(|index| gamepads.nth(index)),
+        }
+    }
+}
+
+/// Every gamepad seen so far, in the order they were first connected. A gamepad keeps its
+/// place while it's disconnected, so the others don't move up and it gets its place back when
+/// it reconnects.
+#[derive(Resource, Default)]
+pub struct GamepadOrder(Vec<Entity>);
+
+/// Finds gamepads by their place in the [`GamepadOrder`].
+#[derive(SystemParam)]
+pub struct PlayerGamepads<'w, 's> {
+    order: Res<'w, GamepadOrder>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+impl PlayerGamepads<'_, '_> {
+    /// The `index`th gamepad, if it's connected.
+    pub fn nth(&self, index: u8) -> Option<&Gamepad> {
+        let entity = *self.order.0.get(index as usize)?;
+
+        self.gamepads.get(entity).ok()
+    }
+}
+
+/// A single key or button, on any device.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button on the player's own gamepad. Triggers count as pressed past their threshold.
+    Gamepad(GamepadButton),
+}
+
+impl Button {
+    pub fn pressed(&self, devices: &Devices) -> bool {
+        match self {
+            Button::Key(key) => devices.keys.pressed(*key),
+            Button::Mouse(button) => devices.mouse.pressed(*button),
+            Button::Gamepad(button) => devices.gamepad.is_some_and(|g| g.pressed(*button)),
+        }
+    }
+
+    pub fn just_pressed(&self, devices: &Devices) -> bool {
+        match self {
+            Button::Key(key) => devices.keys.just_pressed(*key),
+            Button::Mouse(button) => devices.mouse.just_pressed(*button),
+            Button::Gamepad(button) => devices.gamepad.is_some_and(|g| g.just_pressed(*button)),
+        }
+    }
+
+    /// What the button is called, for showing to players.
+    pub fn name(&self) -> String {
+        match self {
+            Button::Key(key) => format!("{key:?}"),
+            Button::Mouse(button) => format!("Mouse {button:?}"),
+            Button::Gamepad(button) => format!("{button:?}"),
+        }
+    }
+}
+
+/// An action that goes from -1 to 1, driven by one set of buttons each way.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct AxisBinding {
+    pub positive: Vec<Button>,
+    pub negative: Vec<Button>,
+}
+
+impl AxisBinding {
+    pub fn value(&self, devices: &Devices) -> f32 {
+        let positive = self.positive.iter().any(|b| b.pressed(devices));
+        let negative = self.negative.iter().any(|b| b.pressed(devices));
+
+        f32::from(positive) - f32::from(negative)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+/// How far a stick has to be pushed before it counts, and how its travel maps to input.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct StickResponse {
+    /// Pushes shorter than this, as a fraction of full travel, are ignored.
+    pub deadzone: f32,
+    /// Travel past the deadzone is raised to this power. Above 1 gives finer control near
+    /// the centre, 1 is linear.
+    pub exponent: f32,
+}
+
+impl Default for StickResponse {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            exponent: 2.,
+        }
+    }
+}
+
+impl StickResponse {
+    /// Applies the deadzone and curve to the stick's distance from centre, keeping its
+    /// direction.
+    pub fn apply(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+        if length <= self.deadzone {
+            return Vec2::ZERO;
+        }
+
+        let travel = ((length - self.deadzone) / (1. - self.deadzone)).min(1.);
+
+        stick / length * travel.powf(self.exponent)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct StickBinding {
+    pub stick: Stick,
+    #[serde(default)]
+    pub response: StickResponse,
+}
+
+impl StickBinding {
+    /// Where the stick points after its response, up being forward. Zero without a gamepad.
+    pub fn value(&self, devices: &Devices) -> Vec2 {
+        let Some(gamepad) = devices.gamepad else {
+            return Vec2::ZERO;
+        };
+
+        self.response.apply(match self.stick {
+            Stick::Left => gamepad.left_stick(),
+            Stick::Right => gamepad.right_stick(),
+        })
+    }
+}
+
+/// What a player aims with.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+pub enum AimBinding {
+    #[default]
+    None,
+    /// Wherever the mouse cursor points on the map.
+    Cursor,
+    /// The direction the stick points, relative to the tank's hull. Letting go of the stick
+    /// keeps the last aim.
+    Stick(StickBinding),
+}
+
+/// The devices one local player plays with, for each action.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerBindings {
+    /// Which gamepad is this player's, counting from 0 in the [`GamepadOrder`].
+    #[serde(default)]
+    pub gamepad: Option<u8>,
+    pub move_forward: AxisBinding,
+    /// Positive turns left.
+    pub turn: AxisBinding,
+    /// Drives both movement axes at once, up for ahead and left for a left turn.
+    #[serde(default)]
+    pub move_stick: Option<StickBinding>,
+    pub aim_at: AimBinding,
+    pub fire: Vec<Button>,
+    pub ability: Vec<Button>,
+}
+
+impl PlayerBindings {
+    pub fn keyboard_and_mouse() -> Self {
+        Self {
+            gamepad: None,
+            move_forward: AxisBinding {
+                positive: vec![Button::Key(KeyCode::KeyW)],
+                negative: vec![Button::Key(KeyCode::KeyS)],
+            },
+            turn: AxisBinding {
+                positive: vec![Button::Key(KeyCode::KeyA)],
+                negative: vec![Button::Key(KeyCode::KeyD)],
+            },
+            move_stick: None,
+            aim_at: AimBinding::Cursor,
+            fire: vec![Button::Mouse(MouseButton::Left)],
+            ability: vec![Button::Mouse(MouseButton::Right)],
+        }
+    }
+
+    /// Twin-stick controls on the `index`th gamepad.
+    pub fn gamepad(index: u8) -> Self {
+        Self {
+            gamepad: Some(index),
+            move_forward: AxisBinding::default(),
+            turn: AxisBinding::default(),
+            move_stick: Some(StickBinding {
+                stick: Stick::Left,
+                response: StickResponse::default(),
+            }),
+            aim_at: AimBinding::Stick(StickBinding {
+                stick: Stick::Right,
+                response: StickResponse::default(),
+            }),
+            fire: vec![Button::Gamepad(GamepadButton::RightTrigger2)],
+            ability: vec![Button::Gamepad(GamepadButton::LeftTrigger2)],
+        }
+    }
+
+    /// What the player plays with, for showing to them.
+    pub fn device_name(&self) -> String {
+        match self.gamepad {
+            Some(index) => format!("Gamepad {}", index + 1),
+            None => "Keyboard and mouse".to_string(),
+        }
+    }
+
+    pub fn slot(&self, slot: BindingSlot) -> &[Button] {
+        match slot {
+            BindingSlot::MoveForward => &self.move_forward.positive,
+            BindingSlot::MoveBackward => &self.move_forward.negative,
+            BindingSlot::TurnLeft => &self.turn.positive,
+            BindingSlot::TurnRight => &self.turn.negative,
+            BindingSlot::Fire => &self.fire,
+            BindingSlot::Ability => &self.ability,
+        }
+    }
+
+    fn slot_mut(&mut self, slot: BindingSlot) -> &mut Vec<Button> {
+        match slot {
+            BindingSlot::MoveForward => &mut self.move_forward.positive,
+            BindingSlot::MoveBackward => &mut self.move_forward.negative,
+            BindingSlot::TurnLeft => &mut self.turn.positive,
+            BindingSlot::TurnRight => &mut self.turn.negative,
+            BindingSlot::Fire => &mut self.fire,
+            BindingSlot::Ability => &mut self.ability,
+        }
+    }
+}
+
+/// Bindings for every local player, in [`LocalPlayer`] order.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputBindings {
+    pub players: Vec<PlayerBindings>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            players: vec![
+                PlayerBindings::keyboard_and_mouse(),
+                PlayerBindings::gamepad(0),
+                PlayerBindings::gamepad(1),
+                PlayerBindings::gamepad(2),
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BevyError> {
+        let text = std::fs::read_to_string(path)?;
+
+        Ok(ron::from_str(&text)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BevyError> {
+        let path = path.as_ref();
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        std::fs::write(
+            path,
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn player(&self, local_player: LocalPlayer) -> Option<&PlayerBindings> {
+        self.players.get(local_player.0 as usize)
+    }
+}
+
+/// A key or button slot that can be rebound on its own.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BindingSlot {
+    MoveForward,
+    MoveBackward,
+    TurnLeft,
+    TurnRight,
+    Fire,
+    Ability,
+}
+
+impl BindingSlot {
+    pub const ALL: [BindingSlot; 6] = [
+        BindingSlot::MoveForward,
+        BindingSlot::MoveBackward,
+        BindingSlot::TurnLeft,
+        BindingSlot::TurnRight,
+        BindingSlot::Fire,
+        BindingSlot::Ability,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BindingSlot::MoveForward => "Move forward",
+            BindingSlot::MoveBackward => "Move backward",
+            BindingSlot::TurnLeft => "Turn left",
+            BindingSlot::TurnRight => "Turn right",
+            BindingSlot::Fire => "Fire",
+            BindingSlot::Ability => "Ability",
+        }
+    }
+}
+
+/// Binds the next key or button pressed to `slot`, in place of whatever was bound to it.
+/// Escape cancels.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct RebindAction {
+    pub player: LocalPlayer,
+    pub slot: BindingSlot,
+}
+
+/// Writes the current [`InputBindings`] to [`INPUT_CONFIG_PATH`].
+#[derive(Message)]
+pub struct SaveInputBindings;
+
+/// A rebind waiting for its key or button.
+#[derive(Resource)]
+pub struct PendingRebind(pub RebindAction);
+
+/// Reads the bindings file, or writes the defaults to it if there is none yet.
+fn load_input_bindings(
+    mut input_bindings: ResMut<InputBindings>,
+    mut save_input_bindings_event_writer: MessageWriter<SaveInputBindings>,
+) {
+    if !Path::new(INPUT_CONFIG_PATH).exists() {
+        save_input_bindings_event_writer.write(SaveInputBindings);
+        return;
+    }
+
+    match InputBindings::load(INPUT_CONFIG_PATH) {
+        Ok(loaded) => *input_bindings = loaded,
+        Err(error) => warn!("Using default input bindings, {INPUT_CONFIG_PATH}: {error}"),
+    }
+}
+
+/// Gamepad entities outlive their connection, so a reconnected gamepad is found again here.
+fn record_gamepads(
+    mut gamepad_order: ResMut<GamepadOrder>,
+    connected: Query<Entity, Added<Gamepad>>,
+) {
+    for entity in connected.iter() {
+        if !gamepad_order.0.contains(&entity) {
+            gamepad_order.0.push(entity);
+        }
+    }
+}
+
+fn start_rebind(
+    mut commands: Commands,
+    mut rebind_action_event_reader: MessageReader<RebindAction>,
+) {
+    if let Some(event) = rebind_action_event_reader.read().last() {
+        info!("Press a key or button for {}", event.slot.name());
+        commands.insert_resource(PendingRebind(*event));
+    }
+}
+
+fn capture_rebind(
+    mut commands: Commands,
+    mut input_bindings: ResMut<InputBindings>,
+    mut save_input_bindings_event_writer: MessageWriter<SaveInputBindings>,
+    pending_rebind: Option<Res<PendingRebind>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: PlayerGamepads,
+) {
+    let Some(pending_rebind) = pending_rebind else {
+        return;
+    };
+
+    let RebindAction { player, slot } = pending_rebind.0;
+
+    // Only the rebinding player's own gamepad is listened to.
+    let gamepad = input_bindings
+        .player(player)
+        .and_then(|p| p.gamepad)
+        .and_then(|index| gamepads.nth(index));
+
+    let button = if keys.just_pressed(KeyCode::Escape) {
+        commands.remove_resource::<PendingRebind>();
+        return;
+    } else if let Some(&key) = keys.get_just_pressed().next() {
+        Button::Key(key)
+    } else if let Some(&mouse_button) = mouse.get_just_pressed().next() {
+        Button::Mouse(mouse_button)
+    } else if let Some(&gamepad_button) = gamepad.and_then(|g| g.get_just_pressed().next()) {
+        Button::Gamepad(gamepad_button)
+    } else {
+        return;
+    };
+
+    commands.remove_resource::<PendingRebind>();
+
+    let Some(player_bindings) = input_bindings.players.get_mut(player.0 as usize) else {
+        return;
+    };
+
+    *player_bindings.slot_mut(slot) = vec![button];
+    save_input_bindings_event_writer.write(SaveInputBindings);
+}
+
+fn save_input_bindings(
+    mut save_input_bindings_event_reader: MessageReader<SaveInputBindings>,
+    input_bindings: Res<InputBindings>,
+) {
+    if save_input_bindings_event_reader.read().count() == 0 {
+        return;
+    }
+
+    if let Err(error) = input_bindings.save(INPUT_CONFIG_PATH) {
+        warn!("Couldn't save input bindings to {INPUT_CONFIG_PATH}: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::{App, Update},
+        ecs::system::RunSystemOnce,
+        input::gamepad::{Gamepad, GamepadSettings},
+    };
+
+    use super::*;
+
+    fn connected(app: &mut App, index: u8) -> bool {
+        app.world_mut()
+            .run_system_once(move |gamepads: PlayerGamepads| gamepads.nth(index).is_some())
+            .unwrap()
+    }
+
+    const RESPONSE: StickResponse = StickResponse {
+        deadzone: 0.2,
+        exponent: 2.,
+    };
+
+    #[test]
+    fn stick_inside_deadzone_is_ignored() {
+        assert_eq!(RESPONSE.apply(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(RESPONSE.apply(Vec2::new(0.1, -0.15)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn stick_travel_past_deadzone_follows_the_curve() {
+        // Halfway between the deadzone and full travel, squared.
+        let halfway = RESPONSE.apply(Vec2::new(0., -0.6));
+        assert!((halfway - Vec2::new(0., -0.25)).length() < 1e-5);
+
+        let linear = StickResponse {
+            exponent: 1.,
+            ..RESPONSE
+        };
+        assert!((linear.apply(Vec2::new(0.6, 0.)) - Vec2::new(0.5, 0.)).length() < 1e-5);
+    }
+
+    #[test]
+    fn full_stick_keeps_its_direction_at_full_length() {
+        let diagonal = RESPONSE.apply(Vec2::ONE);
+
+        assert!((diagonal.length() - 1.).abs() < 1e-5);
+        assert!((diagonal.x - diagonal.y).abs() < 1e-5);
+    }
+
+    #[test]
+    fn disconnected_gamepad_keeps_its_place() {
+        let mut app = App::new();
+        app.init_resource::<GamepadOrder>()
+            .add_systems(Update, record_gamepads);
+
+        let first = app
+            .world_mut()
+            .spawn((Gamepad::default(), GamepadSettings::default()))
+            .id();
+        app.world_mut()
+            .spawn((Gamepad::default(), GamepadSettings::default()));
+        app.update();
+
+        app.world_mut().entity_mut(first).remove::<Gamepad>();
+        app.update();
+
+        assert!(!connected(&mut app, 0));
+        assert!(connected(&mut app, 1));
+
+        app.world_mut().entity_mut(first).insert(Gamepad::default());
+        app.update();
+
+        assert!(connected(&mut app, 0));
+        assert!(connected(&mut app, 1));
+        assert!(!connected(&mut app, 2));
+    }
+}
@@ -0,0 +1,195 @@
+pub mod bindings;
+
+use bevy::{
+    app::{App, Update},
+    camera::Camera,
+    ecs::{
+        query::{Or, With},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Query, Res, ResMut},
+    },
+    input::{ButtonInput, keyboard::KeyCode, mouse::MouseButton},
+    math::{Vec2, Vec3, Vec3Swizzles, primitives::InfinitePlane3d},
+    state::condition::in_state,
+    transform::components::GlobalTransform,
+    window::Window,
+};
+
+use crate::{
+    camera::{MainCamera, SeatCamera},
+    state::MatchState,
+    tank::{Controller, LocalPlayer, PlayerId},
+    tick::TankCommands,
+};
+
+use bindings::{AimBinding, Button, Devices, InputBindings, PlayerGamepads};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(bindings::plugin)
+        .init_resource::<PlayerActions>()
+        .add_systems(
+            Update,
+            (
+                read_actions,
+                apply_actions.run_if(in_state(MatchState::Running)),
+            )
+                .chain(),
+        );
+}
+
+/// How far ahead of the tank a fully pushed aim stick puts the aim point.
+const STICK_AIM_MAX_DISTANCE: f32 = 12.;
+/// How far ahead of the tank a barely pushed aim stick puts the aim point.
+const STICK_AIM_MIN_DISTANCE: f32 = 2.;
+
+/// Where a player wants their turret pointed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Aim {
+    /// A point on the map, in world x/z.
+    At(Vec2),
+    /// A direction relative to the tank's hull, with y ahead and x to the right. Its length,
+    /// up to 1, says how far ahead to aim.
+    Direction(Vec2),
+}
+
+/// A button-like action this frame.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ButtonAction {
+    pub pressed: bool,
+    pub just_pressed: bool,
+}
+
+/// What one local player asks for this frame, whichever devices it came from.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct ActionState {
+    /// From -1 for full reverse to 1 for full ahead.
+    pub move_forward: f32,
+    /// From -1 for a full right turn to 1 for a full left turn.
+    pub turn: f32,
+    /// Nothing when the player isn't aiming anywhere new.
+    pub aim_at: Option<Aim>,
+    pub fire: ButtonAction,
+    /// Nothing uses this yet.
+    pub ability: ButtonAction,
+}
+
+/// Every local player's actions, in [`LocalPlayer`] order.
+#[derive(Resource, Default)]
+pub struct PlayerActions(pub Vec<ActionState>);
+
+impl PlayerActions {
+    pub fn get(&self, local_player: LocalPlayer) -> ActionState {
+        self.0
+            .get(local_player.0 as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn read_actions(
+    mut player_actions: ResMut<PlayerActions>,
+    input_bindings: Res<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: PlayerGamepads,
+    windows: Query<&Window>,
+    cameras: Query<
+        (&Camera, &GlobalTransform, Option<&SeatCamera>),
+        Or<(With<MainCamera>, With<SeatCamera>)>,
+    >,
+) {
+    let cursor = windows.single().ok().and_then(|w| w.cursor_position());
+
+    // Resolved against the player's own part of a split screen, or the shared view when the
+    // screen isn't split.
+    let cursor_aim = |local_player: LocalPlayer| {
+        let cursor = cursor?;
+        let (camera, camera_transform, _) = cameras
+            .iter()
+            .filter(|(camera, ..)| camera.is_active)
+            .find(|(.., seat)| seat.is_none_or(|s| s.0 == local_player))?;
+
+        if !camera.logical_viewport_rect()?.contains(cursor) {
+            return None;
+        }
+
+        let ray = camera.viewport_to_world(camera_transform, cursor).ok()?;
+        let distance = ray.intersect_plane(Vec3::new(0., 1., 0.), InfinitePlane3d::new(Vec3::Y))?;
+
+        Some(ray.get_point(distance).xz())
+    };
+
+    let button_action = |buttons: &[Button], devices: &Devices| ButtonAction {
+        pressed: buttons.iter().any(|b| b.pressed(devices)),
+        just_pressed: buttons.iter().any(|b| b.just_pressed(devices)),
+    };
+
+    player_actions.0 = input_bindings
+        .players
+        .iter()
+        .enumerate()
+        .map(|(index, player_bindings)| {
+            let devices = Devices::of(player_bindings, &keys, &mouse, &gamepads);
+
+            let move_stick = player_bindings
+                .move_stick
+                .map_or(Vec2::ZERO, |s| s.value(&devices));
+
+            ActionState {
+                move_forward: (player_bindings.move_forward.value(&devices) + move_stick.y)
+                    .clamp(-1., 1.),
+                turn: (player_bindings.turn.value(&devices) - move_stick.x).clamp(-1., 1.),
+                aim_at: match player_bindings.aim_at {
+                    AimBinding::None => None,
+                    AimBinding::Cursor => cursor_aim(LocalPlayer(index as u8)).map(Aim::At),
+                    AimBinding::Stick(stick) => Some(stick.value(&devices))
+                        .filter(|&direction| direction != Vec2::ZERO)
+                        .map(Aim::Direction),
+                },
+                fire: button_action(&player_bindings.fire, &devices),
+                ability: button_action(&player_bindings.ability, &devices),
+            }
+        })
+        .collect();
+}
+
+/// Turns each local player's actions into commands for their tank.
+fn apply_actions(
+    player_actions: Res<PlayerActions>,
+    mut tanks: Query<(&mut TankCommands, &Controller, &GlobalTransform), With<PlayerId>>,
+) {
+    for (mut tank_commands, controller, transform) in tanks.iter_mut() {
+        let Controller::User(local_player) = controller else {
+            continue;
+        };
+
+        let actions = player_actions.get(*local_player);
+
+        tank_commands.throttle = actions.move_forward;
+        tank_commands.steer = actions.turn;
+
+        // Without anything new to aim at, the turret keeps its last aim.
+        match actions.aim_at {
+            Some(Aim::At(point)) => tank_commands.aim = Some(point),
+            Some(Aim::Direction(direction)) => {
+                let world_direction = (transform.forward() * direction.y
+                    + transform.right() * direction.x)
+                    .xz()
+                    .normalize_or_zero();
+                let distance = STICK_AIM_MIN_DISTANCE
+                    + (STICK_AIM_MAX_DISTANCE - STICK_AIM_MIN_DISTANCE) * direction.length();
+
+                tank_commands.aim = Some(transform.translation().xz() + world_direction * distance);
+            }
+            None => {}
+        }
+
+        if actions.fire.just_pressed {
+            tank_commands.fire = true;
+        }
+
+        tank_commands.hold_fire = actions.fire.pressed;
+    }
+}
@@ -1,50 +1,94 @@
-use avian3d::{
-    PhysicsPlugins,
-    prelude::{PhysicsDebugPlugin, PhysicsGizmos},
-};
-use bevy::{
-    DefaultPlugins,
-    app::{App, Plugin},
-    color::Color,
-    gizmos::{AppGizmoBuilder, config::GizmoConfig},
-};
-use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
-use entities::lights;
-
-use crate::systems::despawn_entity;
-
-mod camera;
-mod diagnostics;
-mod entities;
-mod maps;
-mod systems;
-mod tank;
-
-pub struct AppPlugin;
-
-impl Plugin for AppPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_plugins((
-            DefaultPlugins,
-            EguiPlugin::default(),
-            WorldInspectorPlugin::new(),
-            PhysicsPlugins::default(),
-            PhysicsDebugPlugin::default(),
-            diagnostics::plugin,
-        ))
-        .add_plugins((
-            camera::plugin,
-            despawn_entity::plugin,
-            lights::plugin,
-            maps::plugin,
-            tank::plugin,
-        ))
-        .insert_gizmo_config(
-            PhysicsGizmos {
-                aabb_color: Some(Color::WHITE),
-                ..Default::default()
-            },
-            GizmoConfig::default(),
-        );
-    }
-}
+use avian3d::{
+    PhysicsPlugins,
+    prelude::{PhysicsDebugPlugin, PhysicsGizmos},
+};
+use bevy::{
+    DefaultPlugins,
+    app::{App, Plugin},
+    color::Color,
+    gizmos::{AppGizmoBuilder, config::GizmoConfig},
+};
+use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
+use entities::lights;
+
+use crate::systems::despawn_entity;
+
+mod ai;
+pub mod bounds;
+mod camera;
+pub mod combat;
+mod diagnostics;
+mod entities;
+mod headless;
+pub mod input;
+pub mod maps;
+mod menu;
+pub mod paint;
+mod respawn;
+pub mod state;
+mod systems;
+pub mod tank;
+pub mod team;
+pub mod tick;
+
+/// Gameplay without any windowing or rendering, so it can run on top of `MinimalPlugins`.
+pub struct SimulationPlugin {
+    /// Runs without `DefaultPlugins` or anyone playing: brings its own asset plugins, skips
+    /// the menus and leaves every tank to the AI.
+    pub headless: bool,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        if self.headless {
+            app.add_plugins(headless::plugin);
+        }
+
+        app.add_plugins(state::plugin);
+
+        app.add_plugins((
+            PhysicsPlugins::default(),
+            ai::plugin,
+            bounds::plugin,
+            combat::plugin,
+            despawn_entity::plugin,
+            maps::plugin,
+            paint::plugin,
+            respawn::plugin,
+            tank::plugin,
+            team::plugin,
+            tick::plugin,
+        ));
+    }
+}
+
+/// Window, rendering, input and debug tooling on top of [`SimulationPlugin`].
+pub struct AppPlugin;
+
+impl Plugin for AppPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            DefaultPlugins,
+            EguiPlugin::default(),
+            WorldInspectorPlugin::new(),
+            SimulationPlugin { headless: false },
+            PhysicsDebugPlugin,
+            diagnostics::plugin,
+        ))
+        .add_plugins((
+            camera::plugin,
+            input::plugin,
+            lights::plugin,
+            menu::plugin,
+            paint::render_plugin,
+            tank::render_plugin,
+        ))
+        .insert_gizmo_config(
+            PhysicsGizmos {
+                aabb_color: Some(Color::WHITE),
+                ..Default::default()
+            },
+            GizmoConfig::default(),
+        );
+    }
+}
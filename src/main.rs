@@ -1,8 +1,27 @@
-use bevy::app::App;
-use paint_tanks::AppPlugin;
+use std::time::Duration;
+
+use bevy::{
+    MinimalPlugins,
+    app::{App, PluginGroup, ScheduleRunnerPlugin, TerminalCtrlCHandlerPlugin},
+    log::LogPlugin,
+};
+use paint_tanks::{AppPlugin, SimulationPlugin};
 
 fn main() {
-    App::new()
-        .add_plugins(AppPlugin)
-        .run();
+    let mut app = App::new();
+
+    if std::env::args().any(|arg| arg == "--headless") {
+        app.add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                1. / 60.,
+            ))),
+            LogPlugin::default(),
+            TerminalCtrlCHandlerPlugin,
+            SimulationPlugin { headless: true },
+        ));
+    } else {
+        app.add_plugins(AppPlugin);
+    }
+
+    app.run();
 }
@@ -0,0 +1,379 @@
+use std::f32::consts::TAU;
+
+use avian3d::prelude::Collider;
+use bevy::{
+    asset::Assets,
+    color::Color,
+    ecs::{hierarchy::ChildOf, relationship::RelatedSpawnerCommands},
+    math::{Quat, Vec2, Vec3, Vec3Swizzles, primitives::Cuboid},
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    transform::components::Transform,
+};
+
+use crate::{
+    maps::{MapLight, SpawnPoint, SpawnTeam},
+    team::TeamId,
+};
+
+const FLOOR_THICKNESS: f32 = 0.5;
+const WALL_HEIGHT: f32 = 2.;
+const WALL_THICKNESS: f32 = 0.5;
+const RAMP_SIZE: Vec3 = Vec3::new(2., 0.3, 4.);
+const RAMP_PITCH: f32 = 0.25;
+/// Obstacles and ramps keep at least this far from every spawn point.
+const SPAWN_CLEARANCE: f32 = 3.;
+/// Gap left between obstacles, wide enough for a tank to fit through.
+const OBSTACLE_SPACING: f32 = 1.5;
+/// How often a piece is re-rolled before the generator gives up on placing it.
+const MAX_PLACEMENT_ATTEMPTS: u32 = 32;
+const LIGHT_HEIGHT: f32 = 8.;
+
+/// Settings for a generated arena. The same settings always give the same arena.
+#[derive(Clone, Debug)]
+pub struct ArenaSettings {
+    pub seed: u64,
+    /// Size of the floor inside the walls.
+    pub size: Vec2,
+    pub teams: u8,
+    pub spawns_per_team: u8,
+    /// Obstacles for each team's share of the arena.
+    pub obstacles: u32,
+    /// Ramps for each team's share of the arena.
+    pub ramps: u32,
+}
+
+impl Default for ArenaSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            size: Vec2::new(30., 30.),
+            teams: 2,
+            spawns_per_team: 2,
+            obstacles: 4,
+            ramps: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlockKind {
+    Floor,
+    Wall,
+    Obstacle,
+    Ramp,
+}
+
+/// One solid box of an arena, placed in the map's local space.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArenaBlock {
+    pub kind: BlockKind,
+    pub size: Vec3,
+    pub transform: Transform,
+}
+
+/// Everything an arena is made of, worked out before anything is spawned.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArenaLayout {
+    pub blocks: Vec<ArenaBlock>,
+    pub spawn_points: Vec<(TeamId, Transform)>,
+}
+
+impl ArenaLayout {
+    /// Lays out an arena from its settings.
+    ///
+    /// Every team gets the same share of the arena, turned to face it: spawn points, obstacles
+    /// and ramps are generated once and then copied round the centre for each team, so no team
+    /// starts with better cover or a shorter drive.
+    pub fn generate(settings: &ArenaSettings) -> Self {
+        let mut random = SplitMix64(settings.seed);
+        let half_size = settings.size / 2.;
+        let teams = settings.teams.max(1);
+
+        let mut layout = Self {
+            blocks: Vec::new(),
+            spawn_points: Vec::new(),
+        };
+
+        layout.add_floor_and_walls(settings.size);
+
+        // Spawn points line up along the team's edge of the arena, facing the centre.
+        let spawns_per_team = settings.spawns_per_team.max(1);
+        let spread = (0.6 / spawns_per_team as f32).min(0.25);
+        for team in 0..teams {
+            for spawn in 0..spawns_per_team {
+                let offset = (spawn as f32 - (spawns_per_team - 1) as f32 / 2.) * spread;
+                let position = rotate_in_arena(Vec2::new(offset, 0.8), team, teams, half_size);
+                let translation = Vec3::new(position.x, 0.5, position.y);
+
+                layout.spawn_points.push((
+                    TeamId(team),
+                    Transform::from_translation(translation)
+                        .looking_at(Vec3::new(0., 0.5, 0.), Vec3::Y),
+                ));
+            }
+        }
+
+        for _ in 0..settings.obstacles {
+            let size = Vec3::new(
+                random.range(1., 2.5),
+                random.range(1., 2.),
+                random.range(1., 2.5),
+            );
+            layout.place(&mut random, BlockKind::Obstacle, size, teams, half_size);
+        }
+
+        for _ in 0..settings.ramps {
+            layout.place(&mut random, BlockKind::Ramp, RAMP_SIZE, teams, half_size);
+        }
+
+        layout
+    }
+
+    fn add_floor_and_walls(&mut self, size: Vec2) {
+        let half_size = size / 2.;
+
+        self.blocks.push(ArenaBlock {
+            kind: BlockKind::Floor,
+            size: Vec3::new(size.x, FLOOR_THICKNESS, size.y),
+            transform: Transform::from_xyz(0., -FLOOR_THICKNESS / 2., 0.),
+        });
+
+        for side in [-1., 1.] {
+            self.blocks.push(ArenaBlock {
+                kind: BlockKind::Wall,
+                size: Vec3::new(size.x + 2. * WALL_THICKNESS, WALL_HEIGHT, WALL_THICKNESS),
+                transform: Transform::from_xyz(
+                    0.,
+                    WALL_HEIGHT / 2.,
+                    side * (half_size.y + WALL_THICKNESS / 2.),
+                ),
+            });
+            self.blocks.push(ArenaBlock {
+                kind: BlockKind::Wall,
+                size: Vec3::new(WALL_THICKNESS, WALL_HEIGHT, size.y),
+                transform: Transform::from_xyz(
+                    side * (half_size.x + WALL_THICKNESS / 2.),
+                    WALL_HEIGHT / 2.,
+                    0.,
+                ),
+            });
+        }
+    }
+
+    /// Places one piece, with a copy for every team, somewhere it fits. Pieces that don't fit
+    /// anywhere are left out.
+    fn place(
+        &mut self,
+        random: &mut SplitMix64,
+        kind: BlockKind,
+        size: Vec3,
+        teams: u8,
+        half_size: Vec2,
+    ) {
+        let radius = size.xz().length() / 2.;
+
+        for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+            let position = Vec2::new(random.range(-0.9, 0.9), random.range(-0.9, 0.9));
+            let yaw = random.range(0., TAU);
+
+            let copies: Vec<(Vec2, f32)> = (0..teams)
+                .map(|team| {
+                    (
+                        rotate_in_arena(position, team, teams, half_size),
+                        yaw + TAU * team as f32 / teams as f32,
+                    )
+                })
+                .collect();
+
+            let fits = copies.iter().enumerate().all(|(index, (copy, _))| {
+                let inside = copy.abs().cmple(half_size - radius).all();
+                let clear_of_spawns = self
+                    .spawn_points
+                    .iter()
+                    .all(|(_, s)| s.translation.xz().distance(*copy) > SPAWN_CLEARANCE + radius);
+                let clear_of_blocks = self
+                    .blocks
+                    .iter()
+                    .filter(|b| matches!(b.kind, BlockKind::Obstacle | BlockKind::Ramp))
+                    .all(|b| {
+                        b.transform.translation.xz().distance(*copy)
+                            > b.size.xz().length() / 2. + radius + OBSTACLE_SPACING
+                    });
+                let clear_of_copies = copies[..index]
+                    .iter()
+                    .all(|(other, _)| other.distance(*copy) > 2. * radius + OBSTACLE_SPACING);
+
+                inside && clear_of_spawns && clear_of_blocks && clear_of_copies
+            });
+
+            if !fits {
+                continue;
+            }
+
+            for (copy, yaw) in copies {
+                let transform = match kind {
+                    // Sunk into the floor so its top face starts level with it.
+                    BlockKind::Ramp => Transform::from_xyz(
+                        copy.x,
+                        size.z / 2. * RAMP_PITCH.sin() - size.y / 2. * RAMP_PITCH.cos(),
+                        copy.y,
+                    )
+                    .with_rotation(Quat::from_rotation_y(yaw) * Quat::from_rotation_x(RAMP_PITCH)),
+                    _ => Transform::from_xyz(copy.x, size.y / 2., copy.y)
+                        .with_rotation(Quat::from_rotation_y(yaw)),
+                };
+
+                self.blocks.push(ArenaBlock {
+                    kind,
+                    size,
+                    transform,
+                });
+            }
+
+            return;
+        }
+    }
+}
+
+/// Turns a point given relative to the arena's half size round the centre, into the given
+/// team's share of the arena.
+fn rotate_in_arena(point: Vec2, team: u8, teams: u8, half_size: Vec2) -> Vec2 {
+    let angle = TAU * team as f32 / teams as f32;
+    Vec2::from_angle(angle).rotate(point) * half_size
+}
+
+/// Spawns an arena's blocks, spawn points and light below its map.
+pub(super) fn spawn_arena(
+    parent: &mut RelatedSpawnerCommands<ChildOf>,
+    layout: &ArenaLayout,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    for block in layout.blocks.iter() {
+        let color = match block.kind {
+            BlockKind::Floor => Color::srgb(0.55, 0.55, 0.5),
+            BlockKind::Wall => Color::srgb(0.3, 0.3, 0.32),
+            BlockKind::Obstacle => Color::srgb(0.42, 0.38, 0.33),
+            BlockKind::Ramp => Color::srgb(0.5, 0.45, 0.35),
+        };
+
+        parent.spawn((
+            Mesh3d(meshes.add(Cuboid::from_size(block.size))),
+            MeshMaterial3d(materials.add(color)),
+            Collider::cuboid(block.size.x, block.size.y, block.size.z),
+            block.transform,
+        ));
+    }
+
+    for (team, transform) in layout.spawn_points.iter() {
+        parent.spawn((SpawnPoint, SpawnTeam(*team), *transform));
+    }
+
+    parent.spawn((MapLight, Transform::from_xyz(0., LIGHT_HEIGHT, 0.)));
+}
+
+/// Small, fast and fully deterministic random numbers, so an arena only depends on its seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`, from the top 24 bits so every value is exact in an `f32`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_settings_give_the_same_arena() {
+        let settings = ArenaSettings::default();
+        let layout = ArenaLayout::generate(&settings);
+
+        assert_eq!(layout, ArenaLayout::generate(&settings));
+        assert_ne!(
+            layout,
+            ArenaLayout::generate(&ArenaSettings {
+                seed: 1,
+                ..settings
+            })
+        );
+    }
+
+    #[test]
+    fn every_team_gets_the_same_spawns() {
+        for teams in 2..=4 {
+            let settings = ArenaSettings {
+                teams,
+                spawns_per_team: 3,
+                ..Default::default()
+            };
+            let layout = ArenaLayout::generate(&settings);
+
+            for team in 0..teams {
+                let spawns = layout
+                    .spawn_points
+                    .iter()
+                    .filter(|(id, _)| *id == TeamId(team))
+                    .count();
+                assert_eq!(spawns, settings.spawns_per_team as usize, "team {team}");
+            }
+        }
+
+        // With two spawns each, every spawn sits at the same distance from the centre.
+        let layout = ArenaLayout::generate(&ArenaSettings::default());
+        let distance = layout.spawn_points[0].1.translation.xz().length();
+        for (team, transform) in layout.spawn_points.iter() {
+            let difference = (transform.translation.xz().length() - distance).abs();
+            assert!(difference < 1e-4, "team {} is {difference} off", team.0);
+        }
+    }
+
+    #[test]
+    fn obstacles_and_ramps_keep_clear_of_spawns() {
+        let mut placed = 0;
+        for seed in 0..100 {
+            let layout = ArenaLayout::generate(&ArenaSettings {
+                seed,
+                ..Default::default()
+            });
+
+            for block in layout
+                .blocks
+                .iter()
+                .filter(|b| matches!(b.kind, BlockKind::Obstacle | BlockKind::Ramp))
+            {
+                placed += 1;
+                for (_, spawn) in layout.spawn_points.iter() {
+                    let gap = block
+                        .transform
+                        .translation
+                        .xz()
+                        .distance(spawn.translation.xz())
+                        - block.size.xz().length() / 2.;
+                    assert!(
+                        gap > SPAWN_CLEARANCE,
+                        "seed {seed}: {:?} {gap} away",
+                        block.kind
+                    );
+                }
+            }
+        }
+        assert!(placed > 0);
+    }
+}
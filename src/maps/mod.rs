@@ -0,0 +1,339 @@
+pub mod arena;
+pub mod registry;
+
+use avian3d::prelude::{Collider, ColliderConstructor, Friction, RigidBody, Sensor};
+use bevy::{
+    app::{App, FixedUpdate},
+    asset::{AssetServer, Assets},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::{ChildOf, Children},
+        message::{MessageReader, MessageWriter},
+        name::Name,
+        observer::On,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::warn,
+    math::{Vec2, Vec3, Vec3Swizzles},
+    mesh::{Mesh, Mesh3d},
+    pbr::StandardMaterial,
+    scene::{SceneInstanceReady, SceneRoot},
+    state::{state::OnEnter, state_scoped::DespawnOnExit},
+    transform::components::Transform,
+};
+
+use crate::{
+    paint::{PaintGrid, PaintSplats},
+    state::{GameState, MatchSettings},
+    systems::despawn_entity::DespawnEntity,
+    team::TeamId,
+    tick::GameplaySystems,
+};
+
+use arena::{ArenaLayout, spawn_arena};
+use registry::{LoadMap, MapChanged, MapRegistry, MapRotation, MapSource};
+
+#[derive(Component)]
+#[require(Transform::from_xyz(0., 0.5, 0.))]
+pub struct SpawnPoint;
+
+/// Keeps a [`SpawnPoint`] for one team. Spawn points without it take anyone.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct SpawnTeam(pub TeamId);
+
+/// An objective area. Its collider is a unit cylinder that the node's scale sizes in the
+/// map scene.
+#[derive(Component)]
+pub struct CaptureZone;
+
+/// Where the map wants a light. Lights are only added by the renderer.
+#[derive(Component)]
+pub struct MapLight;
+
+/// The [`SpawnPoint`] a tank was spawned at, which stays taken until the tank is gone.
+#[derive(Component)]
+#[relationship(relationship_target = SpawnPointOccupant)]
+pub struct SpawnedAt(pub Entity);
+
+#[derive(Component)]
+#[relationship_target(relationship = SpawnedAt)]
+pub struct SpawnPointOccupant(Entity);
+
+#[derive(Component)]
+#[require(PaintSplats)]
+pub struct Map;
+
+/// Where tanks may be on a map, in the map's local space.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MapBounds {
+    /// Size of the playable area, centred on the map's origin.
+    pub size: Vec2,
+    /// Anything that falls below this height is gone for good.
+    pub kill_height: f32,
+}
+
+impl MapBounds {
+    pub fn contains(&self, position: Vec3) -> bool {
+        position.xz().abs().cmple(self.size / 2.).all() && position.y >= self.kill_height
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MapRegistry>()
+        .init_resource::<MapRotation>()
+        .add_message::<LoadMap>()
+        .add_message::<MapChanged>()
+        .add_systems(OnEnter(GameState::InMatch), load_match_map)
+        .add_systems(OnEnter(GameState::RoundResults), rotate_match_map)
+        .add_systems(FixedUpdate, load_map.in_set(GameplaySystems::Simulate))
+        .add_observer(build_map_colliders)
+        .add_observer(read_map_markers);
+}
+
+/// Node name of a spawn point open to every team.
+const SPAWN_MARKER: &str = "spawn";
+/// Node name prefix of a team's spawn point, followed by the team's number counting from 1.
+const TEAM_SPAWN_MARKER: &str = "spawn_team_";
+const CAPTURE_ZONE_MARKER: &str = "capture_zone";
+const LIGHT_MARKER: &str = "light";
+
+/// Map nodes whose name ends in this get no collider at all, e.g. decorations.
+const NO_COLLIDER_SUFFIX: &str = "-nocol";
+/// Map nodes whose name ends in this collide as convex pieces rather than as their exact
+/// triangles, which suits props that move or get pushed around.
+const CONVEX_COLLIDER_SUFFIX: &str = "-convex";
+
+/// Starts a match on the map it was set up with, and carries on the rotation from there.
+fn load_match_map(
+    mut load_map_event_writer: MessageWriter<LoadMap>,
+    mut map_rotation: ResMut<MapRotation>,
+    match_settings: Res<MatchSettings>,
+) {
+    if let Some(index) = map_rotation
+        .order
+        .iter()
+        .position(|m| *m == match_settings.map)
+    {
+        map_rotation.current = index;
+    }
+
+    load_map_event_writer.write(LoadMap {
+        name: match_settings.map.clone(),
+    });
+}
+
+/// Sets the next match up on the map after this one in the rotation, passing over maps without
+/// room for everyone.
+fn rotate_match_map(
+    mut map_rotation: ResMut<MapRotation>,
+    mut match_settings: ResMut<MatchSettings>,
+    map_registry: Res<MapRegistry>,
+) {
+    for _ in 0..map_rotation.order.len() {
+        let Some(map) = map_rotation
+            .advance()
+            .and_then(|name| map_registry.get(name))
+        else {
+            continue;
+        };
+
+        if match_settings.fits(map) {
+            match_settings.set_map(map);
+            return;
+        }
+    }
+}
+
+/// Tears down the loaded map, with everything on it, and spawns the requested one.
+#[allow(clippy::too_many_arguments)]
+fn load_map(
+    mut commands: Commands,
+    mut load_map_event_reader: MessageReader<LoadMap>,
+    mut map_changed_event_writer: MessageWriter<MapChanged>,
+    maps: Query<Entity, With<Map>>,
+    map_registry: Res<MapRegistry>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // Only the last request in a tick matters, the others would be replaced straight away.
+    let Some(event) = load_map_event_reader.read().last() else {
+        return;
+    };
+
+    let Some(map_info) = map_registry.get(&event.name) else {
+        warn!("No map named {} in the registry", event.name);
+        return;
+    };
+
+    // Spawn points, splats and colliders all live below the map, so they go with it.
+    for map in maps.iter() {
+        commands.entity(map).insert(DespawnEntity);
+    }
+
+    let mut map = commands.spawn((
+        Map,
+        RigidBody::Static,
+        Friction::new(0.9),
+        PaintGrid::new(map_info.floor_size),
+        map_info.bounds,
+        Transform::from_xyz(0., 0., 0.),
+        DespawnOnExit(GameState::InMatch),
+    ));
+
+    match &map_info.source {
+        MapSource::Scene(path) => {
+            map.insert(SceneRoot(asset_server.load(path)));
+        }
+        MapSource::Arena(settings) => {
+            let layout = ArenaLayout::generate(settings);
+            map.with_children(|parent| spawn_arena(parent, &layout, &mut meshes, &mut materials));
+        }
+    }
+
+    map_changed_event_writer.write(MapChanged {
+        name: map_info.name.clone(),
+    });
+}
+
+/// Gives every mesh in a map's scene a collider built from the mesh itself, once the scene
+/// has been spawned.
+fn build_map_colliders(
+    scene_instance_ready: On<SceneInstanceReady>,
+    mut commands: Commands,
+    maps: Query<(), With<Map>>,
+    descendants: Query<&Children>,
+    ancestors: Query<&ChildOf>,
+    names: Query<&Name>,
+    meshes: Query<(), With<Mesh3d>>,
+) {
+    let map = scene_instance_ready.event().entity;
+
+    if !maps.contains(map) {
+        return;
+    }
+
+    for mesh in descendants
+        .iter_descendants(map)
+        .filter(|&e| meshes.contains(e))
+    {
+        // glTF puts each mesh primitive on its own entity below the node, so the suffix
+        // may be on any ancestor up to the map.
+        let node_names: Vec<&str> = std::iter::once(mesh)
+            .chain(ancestors.iter_ancestors(mesh))
+            .take_while(|&e| e != map)
+            .filter_map(|e| names.get(e).ok())
+            .map(|n| n.as_str())
+            .collect();
+
+        if node_names.iter().any(|n| n.ends_with(NO_COLLIDER_SUFFIX)) {
+            continue;
+        }
+
+        let collider_constructor = if node_names
+            .iter()
+            .any(|n| n.ends_with(CONVEX_COLLIDER_SUFFIX))
+        {
+            ColliderConstructor::ConvexDecompositionFromMesh
+        } else {
+            ColliderConstructor::TrimeshFromMesh
+        };
+
+        commands.entity(mesh).insert(collider_constructor);
+    }
+}
+
+/// Turns the gameplay markers placed in a map's scene into spawn points, objectives and
+/// lights. Markers are matched by node name, ignoring case and any `.001` style suffix
+/// Blender adds to duplicated nodes.
+fn read_map_markers(
+    scene_instance_ready: On<SceneInstanceReady>,
+    mut commands: Commands,
+    maps: Query<(), With<Map>>,
+    descendants: Query<&Children>,
+    names: Query<&Name>,
+) {
+    let map = scene_instance_ready.event().entity;
+
+    if !maps.contains(map) {
+        return;
+    }
+
+    for (node, name) in descendants
+        .iter_descendants(map)
+        .filter_map(|e| names.get(e).ok().map(|n| (e, n)))
+    {
+        let marker = name
+            .as_str()
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if marker == SPAWN_MARKER {
+            commands.entity(node).insert(SpawnPoint);
+        } else if let Some(team_number) = marker
+            .strip_prefix(TEAM_SPAWN_MARKER)
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|&n| n > 0)
+        {
+            commands
+                .entity(node)
+                .insert((SpawnPoint, SpawnTeam(TeamId(team_number - 1))));
+        } else if marker == CAPTURE_ZONE_MARKER {
+            commands
+                .entity(node)
+                .insert((CaptureZone, Sensor, Collider::cylinder(1., 1.)));
+        } else if marker == LIGHT_MARKER {
+            commands.entity(node).insert(MapLight);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{MinimalPlugins, state::state::State, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::SimulationPlugin;
+
+    #[test]
+    fn rounds_rotate_through_the_maps() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, SimulationPlugin { headless: true }))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                50,
+            )));
+        app.finish();
+        app.cleanup();
+
+        {
+            let mut match_settings = app.world_mut().resource_mut::<MatchSettings>();
+            match_settings.players.clear();
+            match_settings.round_length = Duration::from_secs(1);
+        }
+
+        let mut played = Vec::new();
+        let mut last_state = None;
+        for _ in 0..2000 {
+            app.update();
+
+            let state = *app.world().resource::<State<GameState>>().get();
+            if last_state != Some(state) && state == GameState::InMatch {
+                played.push(app.world().resource::<MatchSettings>().map.clone());
+            }
+            last_state = Some(state);
+
+            if played.len() == 3 {
+                break;
+            }
+        }
+
+        assert_eq!(played, ["Plane", "Arena", "Plane"]);
+    }
+}
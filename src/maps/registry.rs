@@ -0,0 +1,158 @@
+use bevy::{
+    ecs::{message::Message, resource::Resource},
+    math::Vec2,
+};
+
+use super::{MapBounds, arena::ArenaSettings};
+
+/// The ways a round can be played.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    Deathmatch,
+    Paint,
+    Capture,
+}
+
+/// What a map is built from.
+#[derive(Clone, Debug)]
+pub enum MapSource {
+    /// Asset path of a glTF scene.
+    Scene(String),
+    /// Generated from a seed, without any assets.
+    Arena(ArenaSettings),
+}
+
+/// Everything known about a map before it's loaded.
+#[derive(Clone, Debug)]
+pub struct MapInfo {
+    pub name: String,
+    pub source: MapSource,
+    /// How many players can take part. No more are let in when a match is set up.
+    pub max_players: u8,
+    /// How many teams have spawn points of their own, counting up from team 0.
+    pub teams: u8,
+    pub modes: Vec<GameMode>,
+    /// Size of the paintable floor, centred on the map's origin.
+    pub floor_size: Vec2,
+    pub bounds: MapBounds,
+}
+
+/// Every map that can be played.
+#[derive(Resource)]
+pub struct MapRegistry {
+    pub maps: Vec<MapInfo>,
+}
+
+impl Default for MapRegistry {
+    fn default() -> Self {
+        Self {
+            maps: vec![
+                MapInfo {
+                    name: "Plane".to_string(),
+                    source: MapSource::Scene("plane_map.glb#Scene0".to_string()),
+                    max_players: 2,
+                    teams: 2,
+                    modes: vec![GameMode::Deathmatch, GameMode::Paint, GameMode::Capture],
+                    floor_size: Vec2::new(10., 10.),
+                    bounds: MapBounds {
+                        size: Vec2::new(10., 10.),
+                        kill_height: -5.,
+                    },
+                },
+                MapInfo::arena("Arena", ArenaSettings::default()),
+            ],
+        }
+    }
+}
+
+impl MapInfo {
+    /// A generated arena, open to every team and every mode.
+    pub fn arena(name: &str, settings: ArenaSettings) -> Self {
+        Self {
+            name: name.to_string(),
+            max_players: settings.teams.saturating_mul(settings.spawns_per_team),
+            teams: settings.teams.max(1),
+            modes: vec![GameMode::Deathmatch, GameMode::Paint, GameMode::Capture],
+            floor_size: settings.size,
+            bounds: MapBounds {
+                size: settings.size,
+                kill_height: -5.,
+            },
+            source: MapSource::Arena(settings),
+        }
+    }
+}
+
+impl MapRegistry {
+    pub fn get(&self, name: &str) -> Option<&MapInfo> {
+        self.maps.iter().find(|m| m.name == name)
+    }
+}
+
+/// The order maps are played in, by name. It wraps around after the last one.
+#[derive(Resource)]
+pub struct MapRotation {
+    pub order: Vec<String>,
+    pub current: usize,
+}
+
+impl Default for MapRotation {
+    fn default() -> Self {
+        Self {
+            order: vec!["Plane".to_string(), "Arena".to_string()],
+            current: 0,
+        }
+    }
+}
+
+impl MapRotation {
+    pub fn current(&self) -> Option<&str> {
+        self.order.get(self.current).map(String::as_str)
+    }
+
+    pub fn advance(&mut self) -> Option<&str> {
+        if !self.order.is_empty() {
+            self.current = (self.current + 1) % self.order.len();
+        }
+
+        self.current()
+    }
+}
+
+/// Replaces whatever map is loaded with the named one from the [`MapRegistry`].
+#[derive(Message)]
+pub struct LoadMap {
+    pub name: String,
+}
+
+/// Sent once a map has been replaced. The old map, with its spawn points and paint, is
+/// already on its way out.
+#[derive(Message)]
+pub struct MapChanged {
+    pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_wraps_around_to_the_first_map() {
+        let mut map_rotation = MapRotation::default();
+
+        assert_eq!(map_rotation.current(), Some("Plane"));
+        assert_eq!(map_rotation.advance(), Some("Arena"));
+        assert_eq!(map_rotation.advance(), Some("Plane"));
+    }
+
+    #[test]
+    fn empty_rotation_has_no_map() {
+        let mut map_rotation = MapRotation {
+            order: Vec::new(),
+            current: 0,
+        };
+
+        assert_eq!(map_rotation.advance(), None);
+        assert_eq!(map_rotation.current, 0);
+    }
+}
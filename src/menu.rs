@@ -0,0 +1,424 @@
+use bevy::{
+    app::{App, Update},
+    ecs::{
+        bundle::Bundle,
+        component::Component,
+        message::MessageWriter,
+        query::With,
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{
+        ButtonInput,
+        gamepad::{Gamepad, GamepadButton},
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+    log::info,
+    state::{
+        condition::in_state,
+        state::{NextState, OnEnter, State, States},
+        state_scoped::DespawnOnExit,
+    },
+    ui::{AlignItems, JustifyContent, Node, Val, widget::Text},
+};
+
+use crate::{
+    combat::Scoreboard,
+    input::bindings::{
+        BindingSlot, Button, Devices, InputBindings, PendingRebind, PlayerGamepads, RebindAction,
+        RebindSystems,
+    },
+    maps::registry::MapRegistry,
+    state::{GameState, LoadFailures, MatchSettings, MatchState},
+    tank::{Controller, LocalPlayer, PlayerId},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ControlsCursor>()
+        .add_systems(OnEnter(GameState::Loading), spawn_loading_screen)
+        .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+        .add_systems(OnEnter(GameState::Controls), spawn_controls)
+        .add_systems(OnEnter(GameState::MatchSetup), spawn_match_setup)
+        .add_systems(OnEnter(MatchState::Paused), spawn_pause_menu)
+        .add_systems(OnEnter(GameState::RoundResults), spawn_round_results)
+        .add_systems(
+            Update,
+            (
+                update_loading_screen.run_if(in_state(GameState::Loading)),
+                main_menu_input.run_if(in_state(GameState::MainMenu)),
+                // Before the rebind systems, so the Escape that cancels a rebind doesn't also
+                // leave the screen.
+                (
+                    controls_input.before(RebindSystems),
+                    update_controls.after(RebindSystems),
+                )
+                    .run_if(in_state(GameState::Controls)),
+                (match_setup_input, join_seats, update_match_setup)
+                    .chain()
+                    .run_if(in_state(GameState::MatchSetup)),
+                pause_input.run_if(in_state(GameState::InMatch)),
+                round_results_input.run_if(in_state(GameState::RoundResults)),
+            ),
+        );
+}
+
+/// The text of the loading screen, replaced by what went wrong if loading fails.
+#[derive(Component)]
+struct LoadingText;
+
+/// The text of the match setup screen, rewritten whenever the settings change.
+#[derive(Component)]
+struct MatchSetupText;
+
+/// The text of the controls screen, rewritten every frame.
+#[derive(Component)]
+struct ControlsText;
+
+/// Whose bindings the controls screen shows, and which of their slots is picked.
+#[derive(Resource, Default)]
+struct ControlsCursor {
+    player: usize,
+    slot: usize,
+}
+
+/// A full-screen node that centres its text and goes away when `state` is left.
+fn screen<S: States>(state: S) -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        DespawnOnExit(state),
+    )
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn(screen(GameState::Loading))
+        .with_child((Text::new("Loading..."), LoadingText));
+}
+
+fn update_loading_screen(
+    mut texts: Query<&mut Text, With<LoadingText>>,
+    load_failures: Res<LoadFailures>,
+) {
+    if load_failures.0.is_empty() {
+        return;
+    }
+
+    for mut text in texts.iter_mut() {
+        text.0 = format!("Couldn't load the game\n\n{}", load_failures.0.join("\n"));
+    }
+}
+
+fn spawn_main_menu(mut commands: Commands) {
+    commands
+        .spawn(screen(GameState::MainMenu))
+        .with_child(Text::new("Paint Tanks\n\nEnter to play\nC for controls"));
+}
+
+fn spawn_controls(mut commands: Commands) {
+    commands
+        .spawn(screen(GameState::Controls))
+        .with_child((Text::default(), ControlsText));
+}
+
+fn spawn_match_setup(mut commands: Commands) {
+    commands
+        .spawn(screen(GameState::MatchSetup))
+        .with_child((Text::default(), MatchSetupText));
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn(screen(MatchState::Paused))
+        .with_child(Text::new(
+            "Paused\n\nEscape or Start to resume\nQ to leave the match",
+        ));
+}
+
+fn spawn_round_results(mut commands: Commands, scoreboard: Res<Scoreboard>) {
+    let mut players: Vec<&PlayerId> = scoreboard.players.keys().collect();
+    players.sort_by_key(|&&p| {
+        let score = scoreboard.score(p);
+        (std::cmp::Reverse(score.kills), score.deaths, p.0)
+    });
+
+    let mut text = "Round over\n\n".to_string();
+    for &player in players {
+        let score = scoreboard.score(player);
+        text += &format!(
+            "Player {}: {} kills, {} deaths, {} assists, {} damage\n",
+            player.0 + 1,
+            score.kills,
+            score.deaths,
+            score.assists,
+            score.damage_dealt
+        );
+    }
+    text += "\nEnter to continue";
+
+    commands
+        .spawn(screen(GameState::RoundResults))
+        .with_child(Text::new(text));
+}
+
+fn main_menu_input(
+    mut next_game_state: ResMut<NextState<GameState>>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    if input.just_pressed(KeyCode::Enter) {
+        next_game_state.set(GameState::MatchSetup);
+    } else if input.just_pressed(KeyCode::KeyC) {
+        next_game_state.set(GameState::Controls);
+    }
+}
+
+/// Up and down pick a slot, left and right pick whose bindings, and Enter rebinds the slot.
+fn controls_input(
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut rebind_action_event_writer: MessageWriter<RebindAction>,
+    mut controls_cursor: ResMut<ControlsCursor>,
+    input_bindings: Res<InputBindings>,
+    pending_rebind: Option<Res<PendingRebind>>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    // Keys pressed while a rebind waits are the new binding.
+    if pending_rebind.is_some() || input_bindings.players.is_empty() {
+        return;
+    }
+
+    let players = input_bindings.players.len() as i32;
+    let slots = BindingSlot::ALL.len() as i32;
+
+    let player_step = i32::from(input.just_pressed(KeyCode::ArrowRight))
+        - i32::from(input.just_pressed(KeyCode::ArrowLeft));
+    let slot_step = i32::from(input.just_pressed(KeyCode::ArrowDown))
+        - i32::from(input.just_pressed(KeyCode::ArrowUp));
+
+    controls_cursor.player =
+        (controls_cursor.player as i32 + player_step).rem_euclid(players) as usize;
+    controls_cursor.slot = (controls_cursor.slot as i32 + slot_step).rem_euclid(slots) as usize;
+
+    if input.just_pressed(KeyCode::Enter) {
+        rebind_action_event_writer.write(RebindAction {
+            player: LocalPlayer(controls_cursor.player as u8),
+            slot: BindingSlot::ALL[controls_cursor.slot],
+        });
+    } else if input.just_pressed(KeyCode::Escape) {
+        next_game_state.set(GameState::MainMenu);
+    }
+}
+
+fn update_controls(
+    mut texts: Query<&mut Text, With<ControlsText>>,
+    controls_cursor: Res<ControlsCursor>,
+    input_bindings: Res<InputBindings>,
+    pending_rebind: Option<Res<PendingRebind>>,
+) {
+    let Some(player_bindings) = input_bindings.players.get(controls_cursor.player) else {
+        return;
+    };
+
+    let mut slots = String::new();
+    for (index, &slot) in BindingSlot::ALL.iter().enumerate() {
+        let buttons: Vec<String> = player_bindings
+            .slot(slot)
+            .iter()
+            .map(Button::name)
+            .collect();
+
+        slots += &format!(
+            "{} {}: {}\n",
+            if index == controls_cursor.slot {
+                ">"
+            } else {
+                " "
+            },
+            slot.name(),
+            if buttons.is_empty() {
+                "-".to_string()
+            } else {
+                buttons.join(", ")
+            }
+        );
+    }
+
+    let hint = match pending_rebind {
+        Some(pending_rebind) => format!(
+            "Press a key or button for {}, Escape to cancel",
+            pending_rebind.0.slot.name()
+        ),
+        None => "Up and Down to choose, Enter to rebind\nLeft and Right for the other players\n\
+                 Escape for the menu"
+            .to_string(),
+    };
+
+    for mut text in texts.iter_mut() {
+        text.0 = format!(
+            "Controls\n\n< Player {}: {} >\n\n{}\n{}",
+            controls_cursor.player + 1,
+            player_bindings.device_name(),
+            slots,
+            hint
+        );
+    }
+}
+
+fn match_setup_input(
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut match_settings: ResMut<MatchSettings>,
+    map_registry: Res<MapRegistry>,
+    input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let step = i32::from(input.just_pressed(KeyCode::ArrowRight))
+        - i32::from(input.just_pressed(KeyCode::ArrowLeft));
+
+    // Maps without room for everyone already seated are passed over.
+    if step != 0 && !map_registry.maps.is_empty() {
+        let count = map_registry.maps.len() as i32;
+        let current = map_registry
+            .maps
+            .iter()
+            .position(|m| m.name == match_settings.map)
+            .unwrap_or(0) as i32;
+
+        if let Some(map) = (1..=count)
+            .map(|offset| &map_registry.maps[(current + step * offset).rem_euclid(count) as usize])
+            .find(|m| match_settings.fits(m))
+        {
+            match_settings.set_map(map);
+        }
+    }
+
+    let map_fits = map_registry
+        .get(&match_settings.map)
+        .is_some_and(|m| match_settings.fits(m));
+
+    if map_fits
+        && (input.just_pressed(KeyCode::Enter)
+            || gamepads
+                .iter()
+                .any(|g| g.just_pressed(GamepadButton::Start)))
+    {
+        next_game_state.set(GameState::InMatch);
+    } else if input.just_pressed(KeyCode::Escape) {
+        next_game_state.set(GameState::MainMenu);
+    }
+}
+
+/// Each local player's fire claims a seat for them on the emptier team, then moves them to the
+/// next team, and their ability gives the seat back. Nobody can join a map that's full.
+fn join_seats(
+    mut match_settings: ResMut<MatchSettings>,
+    input_bindings: Res<InputBindings>,
+    map_registry: Res<MapRegistry>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: PlayerGamepads,
+) {
+    let Some(map) = map_registry.get(&match_settings.map) else {
+        return;
+    };
+
+    for (index, player_bindings) in input_bindings.players.iter().enumerate() {
+        let controller = Controller::User(LocalPlayer(index as u8));
+        let devices = Devices::of(player_bindings, &keys, &mouse, &gamepads);
+        let just_pressed = |buttons: &[Button]| buttons.iter().any(|b| b.just_pressed(&devices));
+
+        let seated = match_settings
+            .players
+            .iter()
+            .any(|p| p.controller == controller);
+
+        if !seated && just_pressed(&player_bindings.fire) {
+            if !match_settings.join(controller, map) {
+                info!("{} is full", map.name);
+            }
+        } else if seated && just_pressed(&player_bindings.ability) {
+            match_settings.leave(controller);
+        } else if seated && just_pressed(&player_bindings.fire) {
+            match_settings.change_team(controller, map);
+        }
+    }
+}
+
+fn update_match_setup(
+    mut texts: Query<&mut Text, With<MatchSetupText>>,
+    match_settings: Res<MatchSettings>,
+    input_bindings: Res<InputBindings>,
+    map_registry: Res<MapRegistry>,
+) {
+    let max_players = map_registry
+        .get(&match_settings.map)
+        .map_or(0, |m| m.max_players);
+
+    let mut players = String::new();
+    for (index, match_player) in match_settings.players.iter().enumerate() {
+        let device = match match_player.controller {
+            Controller::User(local_player) => input_bindings
+                .player(local_player)
+                .map_or("Local player".to_string(), |p| p.device_name()),
+            Controller::Program => "Computer".to_string(),
+        };
+
+        players += &format!(
+            "Player {}: {}, team {}\n",
+            index + 1,
+            device,
+            match_player.team.0 + 1
+        );
+    }
+
+    for mut text in texts.iter_mut() {
+        text.0 = format!(
+            "Map: < {} >\nPlayers: {}/{}\n\n{}\nFire to join or change team, ability to leave\n\n\
+             Enter or Start to begin\nEscape for the menu",
+            match_settings.map,
+            match_settings.players.len(),
+            max_players,
+            players
+        );
+    }
+}
+
+fn pause_input(
+    match_state: Res<State<MatchState>>,
+    mut next_match_state: ResMut<NextState<MatchState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let toggle = input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|g| g.just_pressed(GamepadButton::Start));
+
+    match match_state.get() {
+        MatchState::Running if toggle => {
+            next_match_state.set(MatchState::Paused);
+        }
+        MatchState::Paused if toggle => {
+            next_match_state.set(MatchState::Running);
+        }
+        MatchState::Paused if input.just_pressed(KeyCode::KeyQ) => {
+            next_game_state.set(GameState::MainMenu);
+        }
+        _ => {}
+    }
+}
+
+fn round_results_input(
+    mut next_game_state: ResMut<NextState<GameState>>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    if input.just_pressed(KeyCode::Enter) {
+        next_game_state.set(GameState::MatchSetup);
+    }
+}
@@ -0,0 +1,504 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    app::{App, FixedUpdate, Update},
+    asset::{Assets, Handle},
+    color::{Color, ColorToPacked},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        message::{Message, MessageReader, MessageWriter},
+        query::{Changed, With},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+        world::{FromWorld, World},
+    },
+    math::{Quat, Vec2, Vec3, Vec3Swizzles, primitives::Circle},
+    mesh::{Mesh, Mesh3d},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    platform::collections::HashMap,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{entities::bullet::ProjectileImpact, maps::Map, team::TeamId, tick::GameplaySystems};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PaintSettings>()
+        .init_resource::<PaintCoverage>()
+        .add_message::<PaintSplat>()
+        .add_systems(
+            FixedUpdate,
+            (
+                splat_projectile_impacts,
+                apply_paint_splats,
+                paint_grid,
+                update_paint_coverage,
+            )
+                .chain()
+                .in_set(GameplaySystems::Simulate),
+        );
+}
+
+pub(super) fn render_plugin(app: &mut App) {
+    app.init_resource::<SplatAssets>()
+        .add_systems(Update, render_paint_splats);
+}
+
+/// Lifts splat discs off the surface they were painted on, so they don't z-fight with it.
+const SPLAT_SURFACE_OFFSET: f32 = 0.01;
+/// Stacks newer splats slightly above older ones, so overlapping splats don't z-fight either.
+const SPLAT_LAYER_OFFSET: f32 = 0.0001;
+const SPLAT_LAYERS: usize = 64;
+
+const PAINT_CELL_SIZE: f32 = 0.25;
+/// Steepest surface, in radians, whose splats still count as painting the floor under them.
+const PAINT_FLOOR_MAX_SLOPE: f32 = std::f32::consts::FRAC_PI_4;
+
+#[derive(Resource)]
+pub struct PaintSettings {
+    pub splat_radius: f32,
+    /// Most splat discs drawn on one map. Past this the oldest discs are moved to show the
+    /// newest splats instead.
+    pub max_splat_discs: usize,
+}
+
+impl Default for PaintSettings {
+    fn default() -> Self {
+        Self {
+            splat_radius: 0.6,
+            max_splat_discs: 1024,
+        }
+    }
+}
+
+#[derive(Message)]
+pub struct PaintSplat {
+    pub map: Entity,
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub team: TeamId,
+    pub color: Color,
+}
+
+/// A single splat, with its position and normal in the map's local space.
+#[derive(Clone, Copy)]
+pub struct Splat {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub radius: f32,
+    pub team: TeamId,
+    pub color: Color,
+}
+
+/// Every splat painted onto a [`Map`], oldest first.
+#[derive(Component, Default)]
+pub struct PaintSplats(pub Vec<Splat>);
+
+/// Which team owns each cell of a [`Map`]'s floor, laid out over the map's local x/z plane
+/// and centred on its origin.
+#[derive(Component)]
+pub struct PaintGrid {
+    origin: Vec2,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Option<TeamId>>,
+    /// How many of the map's splats have already been painted into `cells`.
+    painted_splats: usize,
+}
+
+impl PaintGrid {
+    pub fn new(size: Vec2) -> Self {
+        let columns = (size.x / PAINT_CELL_SIZE).ceil().max(1.) as usize;
+        let rows = (size.y / PAINT_CELL_SIZE).ceil().max(1.) as usize;
+
+        Self {
+            origin: -size / 2.,
+            columns,
+            rows,
+            cells: vec![None; columns * rows],
+            painted_splats: 0,
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn owner(&self, column: usize, row: usize) -> Option<TeamId> {
+        if column < self.columns && row < self.rows {
+            self.cells[row * self.columns + column]
+        } else {
+            None
+        }
+    }
+
+    /// The owner of the cell under `position`, given in the map's local x/z plane.
+    pub fn owner_at(&self, position: Vec2) -> Option<TeamId> {
+        let cell = ((position - self.origin) / PAINT_CELL_SIZE).floor();
+
+        if cell.x < 0. || cell.y < 0. {
+            return None;
+        }
+
+        self.owner(cell.x as usize, cell.y as usize)
+    }
+
+    pub fn owned_cells(&self, team: TeamId) -> usize {
+        self.cells.iter().filter(|c| **c == Some(team)).count()
+    }
+
+    fn cell_center(&self, column: usize, row: usize) -> Vec2 {
+        self.origin + (Vec2::new(column as f32, row as f32) + 0.5) * PAINT_CELL_SIZE
+    }
+
+    /// Gives every cell whose centre lies inside the splat to the splat's team. Splats on walls
+    /// and steep slopes leave the floor alone.
+    fn paint(&mut self, splat: &Splat) {
+        if splat.normal.y < PAINT_FLOOR_MAX_SLOPE.cos() {
+            return;
+        }
+
+        let center = splat.position.xz();
+        let min = ((center - splat.radius - self.origin) / PAINT_CELL_SIZE).floor();
+        let max = ((center + splat.radius - self.origin) / PAINT_CELL_SIZE).ceil();
+
+        let columns = (min.x.max(0.) as usize)..(max.x.max(0.) as usize).min(self.columns);
+        let rows = (min.y.max(0.) as usize)..(max.y.max(0.) as usize).min(self.rows);
+
+        for row in rows {
+            for column in columns.clone() {
+                if self.cell_center(column, row).distance(center) <= splat.radius {
+                    self.cells[row * self.columns + column] = Some(splat.team);
+                }
+            }
+        }
+    }
+}
+
+/// Percentage of painted floor each team owns, across every [`Map`].
+#[derive(Resource, Default)]
+pub struct PaintCoverage {
+    pub teams: HashMap<TeamId, f32>,
+}
+
+impl PaintCoverage {
+    pub fn percentage(&self, team: TeamId) -> f32 {
+        self.teams.get(&team).copied().unwrap_or(0.)
+    }
+}
+
+/// How many of the map's splats have been drawn, and the discs drawing them, oldest first.
+#[derive(Component, Default)]
+struct RenderedSplats {
+    splats: usize,
+    discs: VecDeque<Entity>,
+}
+
+/// What every splat disc is drawn with: one unit disc scaled to each splat, and one material
+/// per paint colour.
+#[derive(Resource)]
+struct SplatAssets {
+    disc: Handle<Mesh>,
+    materials: HashMap<[u8; 4], Handle<StandardMaterial>>,
+}
+
+impl FromWorld for SplatAssets {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            disc: world.resource_mut::<Assets<Mesh>>().add(Circle::new(1.)),
+            materials: HashMap::default(),
+        }
+    }
+}
+
+fn splat_projectile_impacts(
+    mut projectile_impact_event_reader: MessageReader<ProjectileImpact>,
+    mut paint_splat_event_writer: MessageWriter<PaintSplat>,
+    maps: Query<(), With<Map>>,
+    ancestors: Query<&ChildOf>,
+) {
+    for event in projectile_impact_event_reader.read() {
+        // Map colliders sit on the meshes inside the map's scene, not on the map itself.
+        if let Some(map) = std::iter::once(event.surface)
+            .chain(ancestors.iter_ancestors(event.surface))
+            .find(|&e| maps.contains(e))
+        {
+            paint_splat_event_writer.write(PaintSplat {
+                map,
+                position: event.position,
+                normal: event.normal,
+                team: event.team,
+                color: event.color,
+            });
+        }
+    }
+}
+
+fn apply_paint_splats(
+    mut paint_splat_event_reader: MessageReader<PaintSplat>,
+    mut maps: Query<(&mut PaintSplats, &GlobalTransform), With<Map>>,
+    paint_settings: Res<PaintSettings>,
+) {
+    for event in paint_splat_event_reader.read() {
+        if let Ok((mut paint_splats, map_transform)) = maps.get_mut(event.map) {
+            let to_map = map_transform.affine().inverse();
+
+            paint_splats.0.push(Splat {
+                position: to_map.transform_point3(event.position),
+                normal: to_map.transform_vector3(event.normal).normalize_or(Vec3::Y),
+                radius: paint_settings.splat_radius,
+                team: event.team,
+                color: event.color,
+            });
+        }
+    }
+}
+
+fn paint_grid(mut maps: Query<(&PaintSplats, &mut PaintGrid), Changed<PaintSplats>>) {
+    for (paint_splats, mut paint_grid) in maps.iter_mut() {
+        for splat in paint_splats.0.iter().skip(paint_grid.painted_splats) {
+            paint_grid.paint(splat);
+        }
+
+        paint_grid.painted_splats = paint_splats.0.len();
+    }
+}
+
+fn update_paint_coverage(
+    mut paint_coverage: ResMut<PaintCoverage>,
+    paint_grids: Query<&PaintGrid>,
+) {
+    let total_cells: usize = paint_grids.iter().map(|g| g.cells.len()).sum();
+    let mut owned_cells: HashMap<TeamId, usize> = HashMap::default();
+
+    for owner in paint_grids.iter().flat_map(|g| g.cells.iter().flatten()) {
+        *owned_cells.entry(*owner).or_default() += 1;
+    }
+
+    paint_coverage.teams = owned_cells
+        .into_iter()
+        .map(|(team, cells)| (team, cells as f32 / total_cells as f32 * 100.))
+        .collect();
+}
+
+fn render_paint_splats(
+    mut commands: Commands,
+    mut maps: Query<(Entity, &PaintSplats, Option<&mut RenderedSplats>), Changed<PaintSplats>>,
+    mut splat_assets: ResMut<SplatAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    paint_settings: Res<PaintSettings>,
+) {
+    for (map, paint_splats, mut rendered_splats) in maps.iter_mut() {
+        let mut rendered = rendered_splats
+            .as_deref_mut()
+            .map(std::mem::take)
+            .unwrap_or_default();
+
+        for (index, splat) in paint_splats.0.iter().enumerate().skip(rendered.splats) {
+            let offset = SPLAT_SURFACE_OFFSET + (index % SPLAT_LAYERS) as f32 * SPLAT_LAYER_OFFSET;
+
+            let disc_material = splat_assets
+                .materials
+                .entry(splat.color.to_srgba().to_u8_array())
+                .or_insert_with(|| {
+                    materials.add(StandardMaterial {
+                        base_color: splat.color,
+                        unlit: true,
+                        ..Default::default()
+                    })
+                })
+                .clone();
+            let transform = Transform::from_translation(splat.position + splat.normal * offset)
+                .with_rotation(Quat::from_rotation_arc(Vec3::Z, splat.normal))
+                .with_scale(Vec3::splat(splat.radius));
+
+            let disc = if rendered.discs.len() >= paint_settings.max_splat_discs.max(1)
+                && let Some(oldest) = rendered.discs.pop_front()
+            {
+                commands
+                    .entity(oldest)
+                    .try_insert((MeshMaterial3d(disc_material), transform));
+                oldest
+            } else {
+                commands
+                    .spawn((
+                        Mesh3d(splat_assets.disc.clone()),
+                        MeshMaterial3d(disc_material),
+                        transform,
+                        ChildOf(map),
+                    ))
+                    .id()
+            };
+
+            rendered.discs.push_back(disc);
+        }
+
+        rendered.splats = paint_splats.0.len();
+
+        match rendered_splats {
+            Some(mut rendered_splats) => *rendered_splats = rendered,
+            None => {
+                commands.entity(map).insert(rendered);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{MinimalPlugins, state::state::State, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::{
+        SimulationPlugin,
+        entities::turret::{Shoot, Turret},
+        state::{DefaultLoadout, GameState, MatchSettings},
+        tank::{Controller, Loadout, LoadoutDefinition, LocalPlayer, PlayerId, SpawnTank},
+        team::Team,
+    };
+
+    fn splat(x: f32, z: f32, normal: Vec3, team: u8) -> Splat {
+        Splat {
+            position: Vec3::new(x, 0., z),
+            normal,
+            radius: 0.5,
+            team: TeamId(team),
+            color: Color::WHITE,
+        }
+    }
+
+    #[test]
+    fn splat_claims_the_cells_under_it() {
+        let mut grid = PaintGrid::new(Vec2::splat(4.));
+        grid.paint(&splat(0., 0., Vec3::Y, 0));
+
+        assert_eq!(grid.owner_at(Vec2::ZERO), Some(TeamId(0)));
+        assert_eq!(grid.owner_at(Vec2::new(1.5, 1.5)), None);
+        // Cells whose centres are within the radius: a 4x4 block less its corners.
+        assert_eq!(grid.owned_cells(TeamId(0)), 12);
+    }
+
+    #[test]
+    fn later_team_overwrites_earlier() {
+        let mut grid = PaintGrid::new(Vec2::splat(4.));
+        grid.paint(&splat(0., 0., Vec3::Y, 0));
+        grid.paint(&splat(0.25, 0., Vec3::Y, 1));
+
+        assert_eq!(grid.owner_at(Vec2::new(0.1, 0.1)), Some(TeamId(1)));
+        assert_eq!(grid.owner_at(Vec2::new(-0.4, 0.1)), Some(TeamId(0)));
+        assert_eq!(
+            grid.owned_cells(TeamId(0)) + grid.owned_cells(TeamId(1)),
+            16
+        );
+    }
+
+    #[test]
+    fn out_of_bounds_is_ignored() {
+        let mut grid = PaintGrid::new(Vec2::splat(4.));
+        grid.paint(&splat(10., -10., Vec3::Y, 0));
+
+        assert_eq!(grid.owned_cells(TeamId(0)), 0);
+        assert_eq!(grid.owner_at(Vec2::new(10., -10.)), None);
+
+        // Only the part of a splat on the grid counts.
+        grid.paint(&splat(2., 0., Vec3::Y, 0));
+        assert_eq!(grid.owned_cells(TeamId(0)), 6);
+    }
+
+    #[test]
+    fn wall_splat_is_ignored() {
+        let mut grid = PaintGrid::new(Vec2::splat(4.));
+        grid.paint(&splat(0., 0., Vec3::X, 0));
+        grid.paint(&splat(0., 0., Vec3::new(0., 1., 2.).normalize(), 0));
+
+        assert_eq!(grid.owned_cells(TeamId(0)), 0);
+
+        // A gentle ramp is still floor.
+        grid.paint(&splat(0., 0., Vec3::new(0., 2., 1.).normalize(), 0));
+        assert_eq!(grid.owned_cells(TeamId(0)), 12);
+    }
+
+    #[test]
+    fn shots_paint_the_floor_for_the_shooters_team() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, SimulationPlugin { headless: true }))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                16,
+            )));
+        app.finish();
+        app.cleanup();
+        app.world_mut()
+            .resource_mut::<MatchSettings>()
+            .players
+            .clear();
+
+        for _ in 0..200 {
+            app.update();
+            if *app.world().resource::<State<GameState>>().get() == GameState::InMatch {
+                break;
+            }
+        }
+
+        let default_loadout = &app.world().resource::<DefaultLoadout>().0;
+        let loadout = Loadout::from(
+            app.world()
+                .resource::<Assets<LoadoutDefinition>>()
+                .get(default_loadout)
+                .unwrap(),
+        );
+        app.world_mut().write_message(SpawnTank {
+            player: PlayerId(0),
+            controller: Controller::User(LocalPlayer(0)),
+            team: Team {
+                id: TeamId(0),
+                color: Color::WHITE,
+            },
+            loadout,
+        });
+        for _ in 0..30 {
+            app.update();
+        }
+
+        // Aimed down, so the shot lands on the floor just ahead of the tank.
+        let turret = app
+            .world_mut()
+            .query_filtered::<Entity, With<Turret>>()
+            .single(app.world())
+            .unwrap();
+        app.world_mut()
+            .get_mut::<Transform>(turret)
+            .unwrap()
+            .rotate_local_x(-0.6);
+        // Lets the new aim reach the turret's GlobalTransform before it fires.
+        app.update();
+        app.world_mut().write_message(Shoot {
+            turret,
+            held: false,
+        });
+        for _ in 0..60 {
+            app.update();
+        }
+
+        let (splats, grid) = app
+            .world_mut()
+            .query::<(&PaintSplats, &PaintGrid)>()
+            .single(app.world())
+            .unwrap();
+        assert_eq!(splats.0.len(), 1);
+        assert_eq!(grid.owner_at(splats.0[0].position.xz()), Some(TeamId(0)));
+        assert!(
+            app.world()
+                .resource::<PaintCoverage>()
+                .percentage(TeamId(0))
+                > 0.
+        );
+    }
+}
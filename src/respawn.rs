@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate},
+    asset::Assets,
+    ecs::{
+        entity::Entity,
+        message::{MessageReader, MessageWriter},
+        query::{With, Without},
+        resource::Resource,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    state::state::{OnEnter, OnExit},
+    time::{Time, Timer, TimerMode},
+};
+
+use crate::{
+    maps::{SpawnPoint, SpawnPointOccupant, SpawnTeam, registry::MapChanged},
+    state::{DefaultLoadout, GameState, MatchSettings},
+    systems::despawn_entity::DespawnEntity,
+    tank::{
+        Controller, Loadout, LoadoutDefinition, PlayerId, SpawnTank, TankDestroyed, spawn_tank,
+    },
+    team::{Team, TeamId, Teams},
+    tick::GameplaySystems,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RespawnSettings>()
+        .init_resource::<PendingRespawns>()
+        .add_systems(OnEnter(GameState::InMatch), queue_match_players)
+        .add_systems(OnExit(GameState::InMatch), clear_pending_respawns)
+        .add_systems(
+            FixedUpdate,
+            (carry_tanks_to_new_map, queue_respawns, respawn_tanks)
+                .in_set(GameplaySystems::Simulate)
+                // Spawns see which points this tick's respawns took.
+                .before(spawn_tank),
+        );
+}
+
+#[derive(Resource)]
+pub struct RespawnSettings {
+    pub delay: Duration,
+}
+
+impl Default for RespawnSettings {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs(3),
+        }
+    }
+}
+
+pub struct PendingRespawn {
+    pub timer: Timer,
+    pub player: PlayerId,
+    pub controller: Controller,
+    pub team: Team,
+    pub loadout: Loadout,
+}
+
+/// Destroyed players waiting for their respawn delay, or for a spawn point to free up.
+#[derive(Resource, Default)]
+pub struct PendingRespawns(pub Vec<PendingRespawn>);
+
+/// Everyone in the match spawns as soon as the map has a spawn point for them.
+fn queue_match_players(
+    mut pending_respawns: ResMut<PendingRespawns>,
+    match_settings: Res<MatchSettings>,
+    teams: Res<Teams>,
+    default_loadout: Res<DefaultLoadout>,
+    loadout_definitions: Res<Assets<LoadoutDefinition>>,
+) {
+    // Loading doesn't finish until the default loadout has.
+    let Some(default_loadout) = loadout_definitions.get(&default_loadout.0) else {
+        return;
+    };
+    let loadout = Loadout::from(default_loadout);
+
+    for (index, match_player) in match_settings.players.iter().enumerate() {
+        let Some(team) = teams.get(match_player.team) else {
+            continue;
+        };
+
+        pending_respawns.0.push(PendingRespawn {
+            timer: Timer::new(Duration::ZERO, TimerMode::Once),
+            player: PlayerId(index as u8),
+            controller: match_player.controller,
+            team,
+            loadout: loadout.clone(),
+        });
+    }
+}
+
+fn clear_pending_respawns(mut pending_respawns: ResMut<PendingRespawns>) {
+    pending_respawns.0.clear();
+}
+
+fn queue_respawns(
+    mut tank_destroyed_event_reader: MessageReader<TankDestroyed>,
+    mut pending_respawns: ResMut<PendingRespawns>,
+    respawn_settings: Res<RespawnSettings>,
+) {
+    for event in tank_destroyed_event_reader.read() {
+        pending_respawns.0.push(PendingRespawn {
+            timer: Timer::new(respawn_settings.delay, TimerMode::Once),
+            player: event.player,
+            controller: event.controller,
+            team: event.team,
+            loadout: event.loadout.clone(),
+        });
+    }
+}
+
+/// Takes every tank off a map that is being replaced and queues it to spawn on the new one.
+fn carry_tanks_to_new_map(
+    mut commands: Commands,
+    mut map_changed_event_reader: MessageReader<MapChanged>,
+    mut pending_respawns: ResMut<PendingRespawns>,
+    tanks: Query<(Entity, &PlayerId, &Controller, &Team, &Loadout), Without<DespawnEntity>>,
+    respawn_settings: Res<RespawnSettings>,
+) {
+    if map_changed_event_reader.read().count() == 0 {
+        return;
+    }
+
+    for (tank, player, controller, team, loadout) in tanks.iter() {
+        pending_respawns.0.push(PendingRespawn {
+            timer: Timer::new(respawn_settings.delay, TimerMode::Once),
+            player: *player,
+            controller: *controller,
+            team: *team,
+            loadout: loadout.clone(),
+        });
+
+        commands.entity(tank).insert(DespawnEntity);
+    }
+}
+
+fn respawn_tanks(
+    mut spawn_tank_event_writer: MessageWriter<SpawnTank>,
+    mut pending_respawns: ResMut<PendingRespawns>,
+    free_spawn_points: Query<Option<&SpawnTeam>, (With<SpawnPoint>, Without<SpawnPointOccupant>)>,
+    time: Res<Time>,
+) {
+    let mut free_spawn_points: Vec<Option<TeamId>> =
+        free_spawn_points.iter().map(|t| t.map(|t| t.0)).collect();
+
+    for pending_respawn in pending_respawns.0.iter_mut() {
+        pending_respawn.timer.tick(time.delta());
+    }
+
+    pending_respawns.0.retain(|pending_respawn| {
+        if !pending_respawn.timer.is_finished() {
+            return true;
+        }
+
+        // Same preference as spawning: the team's own spawn points, then ones open to anyone.
+        let Some(spawn_point) = free_spawn_points
+            .iter()
+            .position(|&t| t == Some(pending_respawn.team.id))
+            .or_else(|| free_spawn_points.iter().position(|t| t.is_none()))
+        else {
+            return true;
+        };
+
+        free_spawn_points.swap_remove(spawn_point);
+        spawn_tank_event_writer.write(SpawnTank {
+            player: pending_respawn.player,
+            controller: pending_respawn.controller,
+            team: pending_respawn.team,
+            loadout: pending_respawn.loadout.clone(),
+        });
+
+        false
+    });
+}
@@ -0,0 +1,441 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate, Startup, Update},
+    asset::{AssetServer, Handle, RecursiveDependencyLoadState, UntypedHandle},
+    ecs::{
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, common_conditions::resource_equals},
+        system::{Commands, Res, ResMut},
+    },
+    log::error,
+    state::{
+        app::{AppExtStates, StatesPlugin},
+        condition::in_state,
+        state::{NextState, OnEnter, OnExit, State, StateSet, States, SubStates},
+    },
+    time::{Time, Timer, TimerMode, Virtual},
+};
+
+use crate::{
+    maps::registry::MapInfo,
+    tank::{Controller, LoadoutDefinition, LocalPlayer},
+    team::TeamId,
+    tick::GameplaySystems,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    // `DefaultPlugins` bring their own, `MinimalPlugins` don't.
+    if !app.is_plugin_added::<StatesPlugin>() {
+        app.add_plugins(StatesPlugin);
+    }
+
+    app.init_state::<GameState>()
+        .add_sub_state::<MatchState>()
+        .init_resource::<MatchSettings>()
+        .init_resource::<RoundTimer>()
+        .init_resource::<ResultsTimer>()
+        .init_resource::<SkipMenus>()
+        .init_resource::<LoadFailures>()
+        .add_systems(Startup, load_assets)
+        .add_systems(
+            Update,
+            (
+                finish_loading.run_if(in_state(GameState::Loading)),
+                skip_menus.run_if(resource_equals(SkipMenus(true))),
+                leave_results.run_if(in_state(GameState::RoundResults)),
+            ),
+        )
+        .add_systems(OnEnter(GameState::InMatch), start_round)
+        .add_systems(OnEnter(GameState::RoundResults), start_results)
+        .add_systems(OnEnter(MatchState::Paused), pause_time)
+        .add_systems(OnExit(MatchState::Paused), unpause_time)
+        .add_systems(FixedUpdate, end_round.in_set(GameplaySystems::Simulate));
+}
+
+/// Where the game as a whole is. Entities that belong to one of these carry
+/// `DespawnOnExit` with it, so leaving it cleans them up.
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameState {
+    /// Definitions every match needs are still loading.
+    #[default]
+    Loading,
+    MainMenu,
+    /// Rebinding the local players' keys and buttons.
+    Controls,
+    /// Picking the map and who plays.
+    MatchSetup,
+    InMatch,
+    RoundResults,
+}
+
+/// Whether a match is being played or waits for the players.
+#[derive(SubStates, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[source(GameState = GameState::InMatch)]
+pub enum MatchState {
+    #[default]
+    Running,
+    /// Gameplay time stands still, so nothing moves, cools down or respawns.
+    Paused,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MatchPlayer {
+    pub controller: Controller,
+    pub team: TeamId,
+}
+
+/// How the next match is played.
+#[derive(Resource)]
+pub struct MatchSettings {
+    /// Name of the map in the `MapRegistry` the match starts on.
+    pub map: String,
+    /// Everyone taking part. Their position is their `PlayerId`.
+    pub players: Vec<MatchPlayer>,
+    pub round_length: Duration,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            map: "Plane".to_string(),
+            players: vec![
+                MatchPlayer {
+                    controller: Controller::User(LocalPlayer(0)),
+                    team: TeamId(0),
+                },
+                MatchPlayer {
+                    controller: Controller::Program,
+                    team: TeamId(1),
+                },
+            ],
+            round_length: Duration::from_secs(180),
+        }
+    }
+}
+
+impl MatchSettings {
+    /// Whether `map` has room for everyone.
+    pub fn fits(&self, map: &MapInfo) -> bool {
+        self.players.len() <= map.max_players as usize
+    }
+
+    /// Seats `controller` on whichever of `map`'s teams has the fewest players. On a full map a
+    /// computer player gives up their seat for them. False when there's no room at all.
+    pub fn join(&mut self, controller: Controller, map: &MapInfo) -> bool {
+        if self.players.iter().any(|p| p.controller == controller) {
+            return true;
+        }
+
+        if self.players.len() < map.max_players as usize {
+            let team = (0..map.teams.max(1))
+                .map(TeamId)
+                .min_by_key(|&team| self.players.iter().filter(|p| p.team == team).count())
+                .unwrap_or(TeamId(0));
+
+            self.players.push(MatchPlayer { controller, team });
+            return true;
+        }
+
+        match self
+            .players
+            .iter_mut()
+            .find(|p| p.controller == Controller::Program)
+        {
+            Some(computer) => {
+                computer.controller = controller;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn leave(&mut self, controller: Controller) {
+        self.players.retain(|p| p.controller != controller);
+    }
+
+    /// Moves `controller` onto the next of `map`'s teams.
+    pub fn change_team(&mut self, controller: Controller, map: &MapInfo) {
+        for player in self
+            .players
+            .iter_mut()
+            .filter(|p| p.controller == controller)
+        {
+            player.team = TeamId((player.team.0 + 1) % map.teams.max(1));
+        }
+    }
+
+    /// Changes the map, moving anyone on a team it has no spawn points for onto one it does.
+    pub fn set_map(&mut self, map: &MapInfo) {
+        self.map = map.name.clone();
+
+        for player in self.players.iter_mut() {
+            player.team = TeamId(player.team.0 % map.teams.max(1));
+        }
+    }
+}
+
+/// Goes straight from the menus into a match with the current [`MatchSettings`], for when
+/// nobody is there to pick anything.
+#[derive(Resource, Default, PartialEq, Eq)]
+pub struct SkipMenus(pub bool);
+
+/// Time left in the current round.
+#[derive(Resource, Default)]
+pub struct RoundTimer(pub Timer);
+
+/// How long the results stay up before the next match is set up.
+#[derive(Resource, Default)]
+pub struct ResultsTimer(pub Timer);
+
+const RESULTS_DURATION: Duration = Duration::from_secs(10);
+
+/// Assets every match needs, held so they stay loaded between matches.
+#[derive(Resource)]
+struct LoadingAssets(Vec<UntypedHandle>);
+
+/// Why assets every match needs couldn't load. The game stays on the loading screen while
+/// there are any.
+#[derive(Resource, Default)]
+pub struct LoadFailures(pub Vec<String>);
+
+/// What every player's tank is built from when the match starts.
+#[derive(Resource)]
+pub struct DefaultLoadout(pub Handle<LoadoutDefinition>);
+
+const DEFAULT_LOADOUT: &str = "default.loadout.ron";
+
+/// Every turret and tank body definition there is, so a new one only needs its file.
+const DEFINITION_FOLDERS: [&str; 2] = ["turrets", "tank_bodies"];
+
+fn load_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let default_loadout = asset_server.load(DEFAULT_LOADOUT);
+
+    let mut loading_assets = vec![default_loadout.clone().untyped()];
+    loading_assets.extend(
+        DEFINITION_FOLDERS
+            .iter()
+            .map(|folder| asset_server.load_folder(*folder).untyped()),
+    );
+
+    commands.insert_resource(DefaultLoadout(default_loadout));
+    commands.insert_resource(LoadingAssets(loading_assets));
+}
+
+fn finish_loading(
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut load_failures: ResMut<LoadFailures>,
+    loading_assets: Res<LoadingAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut loaded = true;
+
+    // Folders count as loaded once everything in them has, and as failed once any of it has.
+    for handle in loading_assets.0.iter() {
+        match asset_server.recursive_dependency_load_state(handle) {
+            RecursiveDependencyLoadState::Loaded => {}
+            RecursiveDependencyLoadState::Failed(load_error) => {
+                loaded = false;
+
+                let failure = load_error.to_string();
+                if !load_failures.0.contains(&failure) {
+                    error!("{failure}");
+                    load_failures.0.push(failure);
+                }
+            }
+            _ => loaded = false,
+        }
+    }
+
+    if loaded {
+        next_game_state.set(GameState::MainMenu);
+    }
+}
+
+fn skip_menus(
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    match game_state.get() {
+        GameState::MainMenu => next_game_state.set(GameState::MatchSetup),
+        GameState::MatchSetup => next_game_state.set(GameState::InMatch),
+        _ => {}
+    }
+}
+
+fn start_round(mut round_timer: ResMut<RoundTimer>, match_settings: Res<MatchSettings>) {
+    round_timer.0 = Timer::new(match_settings.round_length, TimerMode::Once);
+}
+
+fn end_round(
+    mut round_timer: ResMut<RoundTimer>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+) {
+    if round_timer.0.tick(time.delta()).just_finished() {
+        next_game_state.set(GameState::RoundResults);
+    }
+}
+
+fn start_results(mut results_timer: ResMut<ResultsTimer>) {
+    results_timer.0 = Timer::new(RESULTS_DURATION, TimerMode::Once);
+}
+
+fn leave_results(
+    mut results_timer: ResMut<ResultsTimer>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+) {
+    if results_timer.0.tick(time.delta()).just_finished() {
+        next_game_state.set(GameState::MatchSetup);
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{MinimalPlugins, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::{
+        SimulationPlugin,
+        entities::turret::definition::TurretDefinition,
+        maps::{arena::ArenaSettings, registry::MapInfo},
+    };
+
+    fn map(max_players: u8, teams: u8) -> MapInfo {
+        MapInfo {
+            max_players,
+            teams,
+            ..MapInfo::arena("Test", ArenaSettings::default())
+        }
+    }
+
+    fn user(n: u8) -> Controller {
+        Controller::User(LocalPlayer(n))
+    }
+
+    fn settings(players: &[(Controller, u8)]) -> MatchSettings {
+        MatchSettings {
+            players: players
+                .iter()
+                .map(|&(controller, team)| MatchPlayer {
+                    controller,
+                    team: TeamId(team),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn joins_the_emptiest_team() {
+        let mut settings = settings(&[(user(0), 0)]);
+        let map = map(4, 2);
+
+        assert!(settings.join(user(1), &map));
+        assert!(settings.join(user(2), &map));
+        assert!(settings.join(user(3), &map));
+
+        let teams: Vec<u8> = settings.players.iter().map(|p| p.team.0).collect();
+        assert_eq!(teams, [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn joining_twice_keeps_one_seat() {
+        let mut settings = settings(&[(user(0), 0)]);
+
+        assert!(settings.join(user(0), &map(4, 2)));
+        assert_eq!(settings.players.len(), 1);
+    }
+
+    #[test]
+    fn full_map_gives_a_computer_seat_away() {
+        let mut settings = settings(&[(user(0), 0), (Controller::Program, 1)]);
+
+        assert!(settings.join(user(1), &map(2, 2)));
+        assert_eq!(
+            settings.players[1],
+            MatchPlayer {
+                controller: user(1),
+                team: TeamId(1)
+            }
+        );
+    }
+
+    #[test]
+    fn full_map_of_people_refuses() {
+        let mut settings = settings(&[(user(0), 0), (user(1), 1)]);
+
+        assert!(!settings.join(user(2), &map(2, 2)));
+        assert_eq!(settings.players.len(), 2);
+    }
+
+    #[test]
+    fn team_changes_wrap_around() {
+        let mut settings = settings(&[(user(0), 1)]);
+
+        settings.change_team(user(0), &map(4, 2));
+        assert_eq!(settings.players[0].team, TeamId(0));
+    }
+
+    #[test]
+    fn leaving_frees_the_seat() {
+        let mut settings = settings(&[(user(0), 0), (user(1), 1)]);
+
+        settings.leave(user(0));
+        assert_eq!(settings.players.len(), 1);
+        assert_eq!(settings.players[0].controller, user(1));
+    }
+
+    #[test]
+    fn smaller_map_moves_players_onto_its_teams() {
+        let mut settings = settings(&[(user(0), 0), (user(1), 3)]);
+        let small = map(2, 2);
+
+        assert!(settings.fits(&small));
+        settings.set_map(&small);
+        assert_eq!(settings.map, "Test");
+        assert_eq!(settings.players[1].team, TeamId(1));
+        assert!(!settings.fits(&map(1, 2)));
+    }
+
+    #[test]
+    fn failed_loads_keep_the_game_loading() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, SimulationPlugin { headless: true }))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                50,
+            )))
+            .add_systems(
+                Startup,
+                (|mut loading_assets: ResMut<LoadingAssets>, asset_server: Res<AssetServer>| {
+                    let missing =
+                        asset_server.load::<TurretDefinition>("turrets/missing.turret.ron");
+                    loading_assets.0.push(missing.untyped());
+                })
+                .after(load_assets),
+            );
+        app.finish();
+        app.cleanup();
+
+        for _ in 0..100 {
+            app.update();
+        }
+
+        let load_failures = app.world().resource::<LoadFailures>();
+        assert_eq!(load_failures.0.len(), 1);
+        assert!(load_failures.0[0].contains("missing.turret.ron"));
+        assert_eq!(
+            *app.world().resource::<State<GameState>>().get(),
+            GameState::Loading
+        );
+    }
+}
@@ -1,22 +1,81 @@
-use bevy::{
-    app::{App, Update},
-    ecs::{
-        component::Component,
-        entity::Entity,
-        query::With,
-        system::{Commands, Query},
-    },
-};
-
-pub fn plugin(app: &mut App) {
-    app.add_systems(Update, despawn_entity);
-}
-
-#[derive(Component)]
-pub struct DespawnEntity;
-
-fn despawn_entity(mut commands: Commands, entities_to_despawn: Query<Entity, With<DespawnEntity>>) {
-    for entity in entities_to_despawn.iter() {
-        commands.entity(entity).despawn();
-    }
-}
+use avian3d::prelude::Sleeping;
+use bevy::{
+    app::{App, FixedLast},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::Children,
+        message::MessageReader,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query},
+    },
+    state::{
+        state::{StateTransition, StateTransitionEvent},
+        state_scoped::{DespawnOnExit, despawn_entities_on_exit_state},
+    },
+};
+
+use crate::state::GameState;
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(FixedLast, despawn_entity).add_systems(
+        StateTransition,
+        wake_bodies_leaving_state.before(despawn_entities_on_exit_state::<GameState>),
+    );
+}
+
+#[derive(Component)]
+pub struct DespawnEntity;
+
+fn despawn_entity(
+    mut commands: Commands,
+    entities_to_despawn: Query<Entity, With<DespawnEntity>>,
+    children: Query<&Children>,
+    sleeping: Query<(), With<Sleeping>>,
+) {
+    for entity in entities_to_despawn.iter() {
+        wake_bodies(&mut commands, entity, &children, &sleeping);
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Does for the game state's own despawns what [`despawn_entity`] does for its.
+fn wake_bodies_leaving_state(
+    mut commands: Commands,
+    mut state_transition_event_reader: MessageReader<StateTransitionEvent<GameState>>,
+    scoped: Query<(Entity, &DespawnOnExit<GameState>)>,
+    children: Query<&Children>,
+    sleeping: Query<(), With<Sleeping>>,
+) {
+    let Some(exited) = state_transition_event_reader
+        .read()
+        .last()
+        .filter(|t| t.entered != t.exited)
+        .and_then(|t| t.exited)
+    else {
+        return;
+    };
+
+    for (entity, despawn_on_exit) in scoped.iter() {
+        if despawn_on_exit.0 == exited {
+            wake_bodies(&mut commands, entity, &children, &sleeping);
+        }
+    }
+}
+
+/// A sleeping body wakes its island as it's despawned, by which time it no longer exists to be
+/// woken, so `entity` and everything under it are woken first.
+fn wake_bodies(
+    commands: &mut Commands,
+    entity: Entity,
+    children: &Query<&Children>,
+    sleeping: &Query<(), With<Sleeping>>,
+) {
+    for body in std::iter::once(entity)
+        .chain(children.iter_descendants(entity))
+        .filter(|&e| sleeping.contains(e))
+    {
+        commands.entity(body).remove::<Sleeping>();
+    }
+}
@@ -1 +1 @@
-pub mod despawn_entity;
+pub mod despawn_entity;
@@ -1,183 +1,325 @@
-use bevy::{
-    app::{App, Update},
-    asset::AssetServer,
-    camera::Camera,
-    ecs::{
-        component::Component,
-        entity::Entity,
-        hierarchy::Children,
-        message::{Message, MessageReader, MessageWriter},
-        query::With,
-        system::{Commands, Query, Res},
-    },
-    input::{ButtonInput, keyboard::KeyCode, mouse::MouseButton},
-    math::{Vec3, primitives::InfinitePlane3d},
-    transform::components::{GlobalTransform, Transform},
-    window::Window,
-};
-
-use crate::{
-    camera::MainCamera,
-    entities::{
-        tank_body::{
-            self, Movement, MovementType, TankBodySpawner, basic_tank_body::BasicTankBody,
-        },
-        turret::{self, Shoot, Turret, TurretMovement, TurretSpawner, basic_turret::BasicTurret},
-    },
-    maps::SpawnPoint,
-};
-
-pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((turret::plugin, tank_body::plugin))
-        .add_message::<SpawnTank>()
-        .add_systems(
-            Update,
-            (
-                spawn_tank_keyboard_input,
-                spawn_tank,
-                keyboard_input,
-                mouse_input,
-                mouse_button_input,
-            ),
-        );
-}
-
-#[derive(Message)]
-struct SpawnTank {
-    player: Player,
-    turret: Box<dyn TurretSpawner + Send + Sync>,
-    tank_body: Box<dyn TankBodySpawner + Send + Sync>,
-}
-
-#[derive(Component)]
-pub struct Health(pub u8);
-
-#[derive(Component, Clone, Copy, PartialEq)]
-#[require(Health(100))]
-pub enum Player {
-    User,
-    Program,
-}
-
-fn spawn_tank(
-    mut commands: Commands,
-    mut spawn_tank_event_reader: MessageReader<SpawnTank>,
-    mut spawn_points: Query<(&mut SpawnPoint, &Transform), With<SpawnPoint>>,
-    asset_server: Res<AssetServer>,
-) {
-    for event in spawn_tank_event_reader.read() {
-        if let Some((mut spawn_point, transform)) =
-            spawn_points.iter_mut().filter(|(s, _)| !s.0).nth(0)
-        {
-            spawn_point.0 = true;
-
-            event
-                .tank_body
-                .spawn(&mut commands, &asset_server.as_ref())
-                .insert((event.player, *transform))
-                .with_children(|parent| {
-                    event.turret.spawn_turret(parent, asset_server.as_ref());
-                });
-        }
-    }
-}
-
-fn spawn_tank_keyboard_input(
-    mut spawn_tank_event_writer: MessageWriter<SpawnTank>,
-    spawn_points: Query<&SpawnPoint, With<SpawnPoint>>,
-    input: Res<ButtonInput<KeyCode>>,
-) {
-    if input.just_pressed(KeyCode::Space) {
-        let spawn_point_count = spawn_points.iter().filter(|s| !s.0).count();
-        let player = if spawn_point_count == 2 {
-            Player::User
-        } else {
-            Player::Program
-        };
-
-        spawn_tank_event_writer.write(SpawnTank {
-            player: player,
-            turret: Box::new(BasicTurret {}),
-            tank_body: Box::new(BasicTankBody {}),
-        });
-    }
-}
-
-fn keyboard_input(
-    mut movement_event_writer: MessageWriter<Movement>,
-    input: Res<ButtonInput<KeyCode>>,
-    player: Query<(Entity, &Player), With<Player>>,
-) {
-    if let Some((entity, _)) = player.iter().filter(|(_, p)| **p == Player::User).nth(0) {
-        let forward = input.any_pressed([KeyCode::KeyW]);
-        let backward = input.any_pressed([KeyCode::KeyS]);
-        let left = input.any_pressed([KeyCode::KeyA]);
-        let right = input.any_pressed([KeyCode::KeyD]);
-
-        let linear = forward as i8 - backward as i8;
-        let angular = left as i8 - right as i8;
-
-        movement_event_writer.write(Movement {
-            entity: entity,
-            movement_type: MovementType::Linear(linear),
-        });
-
-        movement_event_writer.write(Movement {
-            entity: entity,
-            movement_type: MovementType::Angular(angular),
-        });
-    }
-}
-
-fn mouse_input(
-    mut turret_movemnt_event_writer: MessageWriter<TurretMovement>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    player_children: Query<(&Children, &Player), With<Player>>,
-    turret_entities: Query<Entity, With<Turret>>,
-) {
-    if let Ok(window) = windows.single()
-        && let Ok((camera, camera_transform)) = camera.single()
-        && let Some(ray) = window
-            .cursor_position()
-            .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor).ok())
-        && let Some(distance) =
-            ray.intersect_plane(Vec3::new(0., 1., 0.), InfinitePlane3d::new(Vec3::Y))
-        && let Some((player_children, _)) = player_children
-            .iter()
-            .filter(|(_, p)| **p == Player::User)
-            .nth(0)
-        && let Some(turret) = player_children
-            .into_iter()
-            .filter(|&c| turret_entities.as_readonly().get(*c).is_ok())
-            .nth(0)
-    {
-        let point = ray.get_point(distance);
-
-        turret_movemnt_event_writer.write(TurretMovement {
-            turret_entity: *turret,
-            x: point.x,
-            z: point.z,
-        });
-    }
-}
-
-fn mouse_button_input(
-    mut shoot_event_writer: MessageWriter<Shoot>,
-    input: Res<ButtonInput<MouseButton>>,
-    player_children: Query<(&Children, &Player), With<Player>>,
-    turret_entities: Query<Entity, With<Turret>>,
-) {
-    if input.just_pressed(MouseButton::Left)
-        && let Some((player_children, _)) = player_children
-            .iter()
-            .filter(|(_, p)| **p == Player::User)
-            .nth(0)
-        && let Some(turret) = player_children
-            .into_iter()
-            .filter(|&c| turret_entities.as_readonly().get(*c).is_ok())
-            .nth(0)
-    {
-        shoot_event_writer.write(Shoot { turret: *turret });
-    }
-}
+use std::{sync::Arc, time::Duration};
+
+use bevy::{
+    app::{App, FixedUpdate},
+    asset::{Asset, AssetApp, AssetServer, Assets},
+    color::Mix,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        error::BevyError,
+        hierarchy::{ChildOf, Children},
+        message::{Message, MessageReader, MessageWriter},
+        observer::On,
+        query::{Changed, With, Without},
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::warn,
+    pbr::{MeshMaterial3d, StandardMaterial},
+    reflect::TypePath,
+    scene::SceneInstanceReady,
+    state::state_scoped::DespawnOnExit,
+    time::{Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+};
+use serde::Deserialize;
+
+use crate::{
+    combat::{DamageLedger, Shooter},
+    entities::{
+        bullet,
+        definition::{Definition, RonDefinitionLoader},
+        tank_body::{self, TankBodySpawner, defined_tank_body::TankBodyFromDefinition},
+        turret::{self, TurretSpawner, defined_turret::TurretFromDefinition},
+    },
+    maps::{SpawnPoint, SpawnPointOccupant, SpawnTeam, SpawnedAt},
+    respawn::{PendingRespawn, PendingRespawns},
+    state::GameState,
+    systems::despawn_entity::DespawnEntity,
+    team::Team,
+    tick::{GameplaySystems, TankCommands},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins((bullet::plugin, turret::plugin, tank_body::plugin))
+        .init_asset::<LoadoutDefinition>()
+        .register_asset_loader(RonDefinitionLoader::<LoadoutDefinition>::new(&[
+            "loadout.ron",
+        ]))
+        .add_message::<SpawnTank>()
+        .add_message::<TankDestroyed>()
+        .add_systems(
+            FixedUpdate,
+            (spawn_tank, destroy_tanks).in_set(GameplaySystems::Simulate),
+        );
+}
+
+pub(super) fn render_plugin(app: &mut App) {
+    app.add_observer(tint_tank);
+}
+
+/// How far a tank's materials are pulled towards its team colour.
+const TEAM_TINT: f32 = 0.6;
+
+#[derive(Message)]
+pub struct SpawnTank {
+    pub player: PlayerId,
+    pub controller: Controller,
+    pub team: Team,
+    pub loadout: Loadout,
+}
+
+/// Sent once when a tank runs out of [`Health`], just before it is despawned.
+#[derive(Message)]
+pub struct TankDestroyed {
+    pub tank: Entity,
+    pub player: PlayerId,
+    pub controller: Controller,
+    pub team: Team,
+    pub loadout: Loadout,
+    pub cause: DeathCause,
+    /// Whoever landed the last damaging hit, unless the tank did it to itself or left the map.
+    pub killer: Option<Shooter>,
+    /// Every other player who damaged the tank during this life, most damage first.
+    pub assists: Vec<PlayerId>,
+}
+
+/// The parts a tank was built from, kept on the tank so it can be rebuilt after dying.
+#[derive(Component, Clone)]
+pub struct Loadout {
+    pub turret: Arc<dyn TurretSpawner + Send + Sync>,
+    pub tank_body: Arc<dyn TankBodySpawner + Send + Sync>,
+}
+
+impl From<&LoadoutDefinition> for Loadout {
+    fn from(definition: &LoadoutDefinition) -> Self {
+        Self {
+            turret: Arc::new(TurretFromDefinition {
+                path: definition.turret.clone().into(),
+            }),
+            tank_body: Arc::new(TankBodyFromDefinition {
+                path: definition.tank_body.clone().into(),
+            }),
+        }
+    }
+}
+
+/// Which definitions a [`Loadout`] is built from, loaded from a `.loadout.ron` file.
+#[derive(Asset, TypePath, Deserialize)]
+pub struct LoadoutDefinition {
+    /// Path of a `.turret.ron` definition.
+    pub turret: String,
+    /// Path of a `.tank_body.ron` definition.
+    pub tank_body: String,
+}
+
+impl Definition for LoadoutDefinition {
+    fn validate(&self) -> Result<(), BevyError> {
+        if !self.turret.ends_with(".turret.ron") {
+            return Err(format!("turret must be a .turret.ron file, not {}", self.turret).into());
+        }
+        if !self.tank_body.ends_with(".tank_body.ron") {
+            return Err(format!(
+                "tank_body must be a .tank_body.ron file, not {}",
+                self.tank_body
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct Health(pub u8);
+
+/// What ends a tank's life once its [`Health`] runs out. Whatever takes the last of it sets
+/// this first.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeathCause {
+    /// Shot to pieces.
+    #[default]
+    Destroyed,
+    /// Dropped below the map's kill plane.
+    Fell,
+    /// Stayed outside the map's bounds for too long.
+    OutOfBounds,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[require(Health(100), DeathCause, TankCommands, DamageLedger)]
+pub struct PlayerId(pub u8);
+
+/// Who drives a tank: someone at this machine, or the game itself.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Controller {
+    User(LocalPlayer),
+    Program,
+}
+
+/// One of the people playing at this machine, counting from 0.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LocalPlayer(pub u8);
+
+/// Puts each requested tank on a free spawn point. Requests with nowhere to go wait in the
+/// [`PendingRespawns`] queue for a point to free up.
+#[allow(clippy::type_complexity)]
+pub(crate) fn spawn_tank(
+    mut commands: Commands,
+    mut spawn_tank_event_reader: MessageReader<SpawnTank>,
+    mut pending_respawns: ResMut<PendingRespawns>,
+    spawn_points: Query<
+        (Entity, &GlobalTransform, Option<&SpawnTeam>),
+        (With<SpawnPoint>, Without<SpawnPointOccupant>),
+    >,
+    asset_server: Res<AssetServer>,
+) {
+    // Occupants are only recorded once commands are applied, so remember this frame's picks.
+    let mut taken_spawn_points = Vec::new();
+
+    for event in spawn_tank_event_reader.read() {
+        let mut free_spawn_points = spawn_points
+            .iter()
+            .filter(|(s, _, _)| !taken_spawn_points.contains(s));
+
+        // The team's own spawn points come first, ones open to anyone after.
+        let Some((spawn_point, spawn_transform, _)) = free_spawn_points
+            .clone()
+            .find(|(_, _, t)| t.is_some_and(|t| t.0 == event.team.id))
+            .or_else(|| free_spawn_points.find(|(_, _, t)| t.is_none()))
+        else {
+            warn!(
+                "No free spawn point for player {}, waiting for one",
+                event.player.0
+            );
+            pending_respawns.0.push(PendingRespawn {
+                timer: Timer::new(Duration::ZERO, TimerMode::Once),
+                player: event.player,
+                controller: event.controller,
+                team: event.team,
+                loadout: event.loadout.clone(),
+            });
+            continue;
+        };
+
+        taken_spawn_points.push(spawn_point);
+
+        // Spawn points may sit anywhere in a map's scene, so only their world placement
+        // means anything to a tank.
+        let (_, rotation, translation) = spawn_transform.to_scale_rotation_translation();
+
+        event
+            .loadout
+            .tank_body
+            .spawn(&mut commands, asset_server.as_ref())
+            .insert((
+                event.player,
+                event.controller,
+                event.team,
+                event.loadout.clone(),
+                SpawnedAt(spawn_point),
+                DespawnOnExit(GameState::InMatch),
+                Transform::from_translation(translation).with_rotation(rotation),
+            ))
+            .with_children(|parent| {
+                event
+                    .loadout
+                    .turret
+                    .spawn_turret(parent, asset_server.as_ref());
+            });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn destroy_tanks(
+    mut commands: Commands,
+    mut tank_destroyed_event_writer: MessageWriter<TankDestroyed>,
+    tanks: Query<
+        (
+            Entity,
+            &Health,
+            &PlayerId,
+            &Controller,
+            &Team,
+            &Loadout,
+            &DamageLedger,
+            &DeathCause,
+        ),
+        (Changed<Health>, Without<DespawnEntity>),
+    >,
+) {
+    for (tank, health, player, controller, team, loadout, damage_ledger, death_cause) in
+        tanks.iter()
+    {
+        if health.0 == 0 {
+            let killer = damage_ledger
+                .last_hit
+                .clone()
+                .filter(|k| k.player != *player && *death_cause == DeathCause::Destroyed);
+
+            tank_destroyed_event_writer.write(TankDestroyed {
+                tank,
+                player: *player,
+                controller: *controller,
+                team: *team,
+                loadout: loadout.clone(),
+                cause: *death_cause,
+                assists: damage_ledger.assists(*player, killer.as_ref().map(|k| k.player)),
+                killer,
+            });
+
+            commands.entity(tank).insert(DespawnEntity);
+        }
+    }
+}
+
+fn tint_tank(
+    scene_instance_ready: On<SceneInstanceReady>,
+    ancestors: Query<&ChildOf>,
+    descendants: Query<&Children>,
+    teams: Query<&Team>,
+    mut mesh_materials: Query<&mut MeshMaterial3d<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let scene_root = scene_instance_ready.event().entity;
+
+    let Some(team) = std::iter::once(scene_root)
+        .chain(ancestors.iter_ancestors(scene_root))
+        .find_map(|e: Entity| teams.get(e).ok())
+    else {
+        return;
+    };
+
+    for descendant in descendants.iter_descendants(scene_root) {
+        if let Ok(mut mesh_material) = mesh_materials.get_mut(descendant)
+            && let Some(material) = materials.get(&mesh_material.0)
+        {
+            let mut tinted_material = material.clone();
+            tinted_material.base_color = material.base_color.mix(&team.color, TEAM_TINT);
+
+            mesh_material.0 = materials.add(tinted_material);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_loadout_is_valid() {
+        let definition: LoadoutDefinition =
+            ron::from_str(include_str!("../assets/default.loadout.ron")).unwrap();
+        assert!(definition.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_to_the_wrong_definitions() {
+        let swapped = LoadoutDefinition {
+            turret: "tank_bodies/basic.tank_body.ron".into(),
+            tank_body: "turrets/basic.turret.ron".into(),
+        };
+        assert!(swapped.validate().is_err());
+    }
+}
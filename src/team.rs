@@ -0,0 +1,48 @@
+use bevy::{
+    app::App,
+    color::Color,
+    ecs::{component::Component, resource::Resource},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Teams>();
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TeamId(pub u8);
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Team {
+    pub id: TeamId,
+    pub color: Color,
+}
+
+/// Every team that can take part in a match, in [`TeamId`] order.
+#[derive(Resource)]
+pub struct Teams(pub Vec<Team>);
+
+impl Teams {
+    pub fn get(&self, id: TeamId) -> Option<Team> {
+        self.0.iter().find(|t| t.id == id).copied()
+    }
+}
+
+impl Default for Teams {
+    fn default() -> Self {
+        Self(
+            [
+                Color::srgba(1., 0.0, 0.0, 1.0),
+                Color::srgba(0.0, 0.0, 1., 1.0),
+                Color::srgba(0.0, 1., 0.0, 1.0),
+                Color::srgba(1., 1., 0.0, 1.0),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(id, color)| Team {
+                id: TeamId(id as u8),
+                color,
+            })
+            .collect(),
+        )
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::{
+    app::{App, FixedFirst, FixedUpdate},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::Children,
+        message::MessageWriter,
+        query::With,
+        resource::Resource,
+        schedule::{IntoScheduleConfigs, SystemSet},
+        system::{Query, ResMut},
+    },
+    math::Vec2,
+    state::condition::in_state,
+    time::{Fixed, Time},
+};
+
+use crate::{
+    entities::{
+        tank_body::{Movement, MovementType},
+        turret::{Shoot, Turret, TurretMovement},
+    },
+    state::GameState,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+        .init_resource::<Tick>()
+        .configure_sets(
+            FixedUpdate,
+            (
+                GameplaySystems::Decide,
+                GameplaySystems::Command,
+                GameplaySystems::Simulate,
+            )
+                .chain()
+                .run_if(in_state(GameState::InMatch)),
+        )
+        .add_systems(FixedFirst, advance_tick)
+        .add_systems(
+            FixedUpdate,
+            issue_tank_commands.in_set(GameplaySystems::Command),
+        );
+}
+
+/// Gameplay ticks per second. Physics steps in lockstep with these ticks.
+pub const TICK_RATE: f64 = 60.;
+
+/// The number of fixed gameplay ticks run so far.
+#[derive(Resource, Default)]
+pub struct Tick(pub u64);
+
+/// Ordering of the gameplay systems within each [`FixedUpdate`] tick.
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameplaySystems {
+    /// Controllers that run inside the tick, like the AI, fill in [`TankCommands`].
+    Decide,
+    /// [`TankCommands`] are turned into gameplay messages.
+    Command,
+    /// The messages are applied to the world.
+    Simulate,
+}
+
+/// What a tank's controller wants it to do on the next tick.
+///
+/// Input is sampled into this every frame and consumed once per tick, so a frame rate that
+/// differs from the tick rate can't change how far a tank moves or how often it fires.
+#[derive(Component, Default, Clone, Copy, PartialEq, Debug)]
+pub struct TankCommands {
+    /// From -1 for full reverse to 1 for full ahead.
+    pub throttle: f32,
+    /// From -1 for a full right turn to 1 for a full left turn.
+    pub steer: f32,
+    /// Point on the map, in world x/z, for the turret to turn towards.
+    pub aim: Option<Vec2>,
+    /// Latched until the next tick consumes it, so a short click between ticks isn't lost.
+    pub fire: bool,
+    /// The fire button is being held down, which keeps automatic weapons firing.
+    pub hold_fire: bool,
+}
+
+fn advance_tick(mut tick: ResMut<Tick>) {
+    tick.0 += 1;
+}
+
+fn issue_tank_commands(
+    mut movement_event_writer: MessageWriter<Movement>,
+    mut turret_movement_event_writer: MessageWriter<TurretMovement>,
+    mut shoot_event_writer: MessageWriter<Shoot>,
+    mut tanks: Query<(Entity, &mut TankCommands, &Children)>,
+    turrets: Query<(), With<Turret>>,
+) {
+    for (entity, mut tank_commands, children) in tanks.iter_mut() {
+        movement_event_writer.write(Movement {
+            entity,
+            movement_type: MovementType::Throttle(tank_commands.throttle),
+        });
+
+        movement_event_writer.write(Movement {
+            entity,
+            movement_type: MovementType::Steer(tank_commands.steer),
+        });
+
+        let Some(&turret) = children.iter().find(|&&c| turrets.contains(c)) else {
+            continue;
+        };
+
+        if let Some(aim) = tank_commands.aim {
+            turret_movement_event_writer.write(TurretMovement {
+                turret_entity: turret,
+                x: aim.x,
+                z: aim.y,
+            });
+        }
+
+        if tank_commands.fire {
+            shoot_event_writer.write(Shoot {
+                turret,
+                held: false,
+            });
+            tank_commands.fire = false;
+        } else if tank_commands.hold_fire {
+            shoot_event_writer.write(Shoot { turret, held: true });
+        }
+    }
+}